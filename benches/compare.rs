@@ -0,0 +1,150 @@
+//! Compares `ThinCell` against the standard library types it replaces.
+//!
+//! Run with `cargo bench`, or e.g. `cargo bench -- borrow` for a single group.
+//! Criterion keeps the results of the previous run and reports the change, so
+//! running it before and after a change of the layout or state shows the
+//! regression.
+
+use std::{
+    cell::RefCell,
+    hint::black_box,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use thin_cell::{sync, unsync};
+
+trait Shape {
+    fn area(&self) -> f64;
+    fn scale(&mut self, factor: f64);
+}
+
+struct Square(f64);
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.0 *= factor;
+    }
+}
+
+fn bench_new(c: &mut Criterion) {
+    let mut group = c.benchmark_group("new");
+    group.bench_function("unsync::ThinCell", |b| {
+        b.iter(|| unsync::ThinCell::new(black_box(42u64)))
+    });
+    group.bench_function("sync::ThinCell", |b| {
+        b.iter(|| sync::ThinCell::new(black_box(42u64)))
+    });
+    group.bench_function("Rc<RefCell<T>>", |b| {
+        b.iter(|| Rc::new(RefCell::new(black_box(42u64))))
+    });
+    group.bench_function("Arc<Mutex<T>>", |b| {
+        b.iter(|| Arc::new(Mutex::new(black_box(42u64))))
+    });
+    group.finish();
+}
+
+fn bench_clone_drop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clone+drop");
+
+    let cell = unsync::ThinCell::new(42u64);
+    group.bench_function("unsync::ThinCell", |b| {
+        b.iter(|| drop(black_box(cell.clone())))
+    });
+
+    let cell = sync::ThinCell::new(42u64);
+    group.bench_function("sync::ThinCell", |b| {
+        b.iter(|| drop(black_box(cell.clone())))
+    });
+
+    let rc = Rc::new(RefCell::new(42u64));
+    group.bench_function("Rc<RefCell<T>>", |b| b.iter(|| drop(black_box(rc.clone()))));
+
+    let arc = Arc::new(Mutex::new(42u64));
+    group.bench_function("Arc<Mutex<T>>", |b| b.iter(|| drop(black_box(arc.clone()))));
+
+    group.finish();
+}
+
+fn bench_borrow(c: &mut Criterion) {
+    let mut group = c.benchmark_group("borrow");
+
+    let cell = unsync::ThinCell::new(0u64);
+    group.bench_function("unsync::ThinCell", |b| {
+        b.iter(|| *black_box(&cell).borrow() += 1)
+    });
+
+    let cell = sync::ThinCell::new(0u64);
+    group.bench_function("sync::ThinCell", |b| {
+        b.iter(|| *black_box(&cell).borrow() += 1)
+    });
+
+    let rc = Rc::new(RefCell::new(0u64));
+    group.bench_function("Rc<RefCell<T>>", |b| {
+        b.iter(|| *black_box(&rc).borrow_mut() += 1)
+    });
+
+    let arc = Arc::new(Mutex::new(0u64));
+    group.bench_function("Arc<Mutex<T>>", |b| {
+        b.iter(|| *black_box(&arc).lock().unwrap() += 1)
+    });
+
+    group.finish();
+}
+
+fn bench_dyn_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dyn dispatch");
+
+    let cell: unsync::ThinCell<dyn Shape> =
+        unsafe { unsync::ThinCell::new_unsize(Square(1.0), |p| p as _) };
+    group.bench_function("unsync::ThinCell<dyn>", |b| {
+        b.iter(|| {
+            let mut shape = cell.borrow();
+            shape.scale(black_box(1.0));
+            shape.area()
+        })
+    });
+
+    let cell: sync::ThinCell<dyn Shape> =
+        unsafe { sync::ThinCell::new_unsize(Square(1.0), |p| p as _) };
+    group.bench_function("sync::ThinCell<dyn>", |b| {
+        b.iter(|| {
+            let mut shape = cell.borrow();
+            shape.scale(black_box(1.0));
+            shape.area()
+        })
+    });
+
+    let rc: Rc<RefCell<dyn Shape>> = Rc::new(RefCell::new(Square(1.0)));
+    group.bench_function("Rc<RefCell<dyn>>", |b| {
+        b.iter(|| {
+            let mut shape = rc.borrow_mut();
+            shape.scale(black_box(1.0));
+            shape.area()
+        })
+    });
+
+    let mut boxed: Box<dyn Shape> = Box::new(Square(1.0));
+    group.bench_function("Box<dyn>", |b| {
+        b.iter(|| {
+            boxed.scale(black_box(1.0));
+            boxed.area()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_new,
+    bench_clone_drop,
+    bench_borrow,
+    bench_dyn_dispatch
+);
+criterion_main!(benches);
@@ -0,0 +1,137 @@
+//! Runs arbitrary sequences of clones, drops, borrows, `leak`/`from_raw` round
+//! trips, `unsize` and `downcast` on cells of both flavours, exercising the
+//! reconstruction of pointers and metadata.
+//!
+//! Run with `cargo +nightly fuzz run cell_ops`, which builds with
+//! AddressSanitizer by default.
+
+#![no_main]
+
+use std::any::Any;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    New(u64),
+    Clone(u8),
+    Drop(u8),
+    Write(u8, u64),
+    Borrow(u8),
+    Leak(u8),
+    LeakTagged(u8, u8),
+    TryUnwrap(u8),
+    Unsize(u8),
+    CloneErased(u8),
+    DropErased(u8),
+    Downcast(u8),
+    DowncastWrong(u8),
+}
+
+/// Picks an element of a collection of `len` elements from a fuzzed index.
+fn pick(len: usize, i: u8) -> Option<usize> {
+    (len > 0).then(|| i as usize % len)
+}
+
+macro_rules! run {
+    ($flavour:ident, $ops:expr) => {{
+        use thin_cell::$flavour::ThinCell;
+
+        let mut cells: Vec<ThinCell<u64>> = Vec::new();
+        let mut erased: Vec<ThinCell<dyn Any>> = Vec::new();
+
+        for op in $ops {
+            match *op {
+                Op::New(value) => cells.push(ThinCell::new(value)),
+                Op::Clone(i) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        cells.push(cells[i].clone());
+                    }
+                }
+                Op::Drop(i) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        cells.swap_remove(i);
+                    }
+                }
+                Op::Write(i, value) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        *cells[i].borrow() = value;
+                        assert_eq!(*cells[i].clone().borrow(), value);
+                    }
+                }
+                Op::Borrow(i) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        let guard = cells[i].borrow();
+                        assert!(cells[i].clone().try_borrow().is_none());
+                        drop(guard);
+                    }
+                }
+                Op::Leak(i) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        let ptr = cells.swap_remove(i).leak();
+                        // SAFETY: `ptr` was just leaked from a `ThinCell<u64>`
+                        cells.push(unsafe { ThinCell::from_raw(ptr) });
+                    }
+                }
+                Op::LeakTagged(i, tag) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        let tag = tag & thin_cell::raw::TAG_MASK as u8;
+                        let ptr = cells.swap_remove(i).leak_tagged(tag);
+                        // SAFETY: `ptr` was just leaked from a `ThinCell<u64>`
+                        let (cell, found) = unsafe { ThinCell::from_raw_tagged(ptr) };
+                        assert_eq!(found, tag);
+                        cells.push(cell);
+                    }
+                }
+                Op::TryUnwrap(i) => {
+                    if let Some(i) = pick(cells.len(), i) {
+                        let cell = cells.swap_remove(i);
+                        let unique = cell.count() == 1;
+                        match cell.try_unwrap() {
+                            Ok(_) => assert!(unique),
+                            Err(cell) => {
+                                assert!(!unique);
+                                cells.push(cell);
+                            }
+                        }
+                    }
+                }
+                Op::Unsize(i) => {
+                    // Unsizing a shared cell whose header holds other metadata panics
+                    if let Some(i) = pick(cells.len(), i).filter(|&i| cells[i].count() == 1) {
+                        let cell = cells.swap_remove(i);
+                        // SAFETY: `Inner<u64>` coerces to `Inner<dyn Any>`
+                        erased.push(unsafe { cell.unsize::<dyn Any>(|p| p as _) });
+                    }
+                }
+                Op::CloneErased(i) => {
+                    if let Some(i) = pick(erased.len(), i) {
+                        erased.push(erased[i].clone());
+                    }
+                }
+                Op::DropErased(i) => {
+                    if let Some(i) = pick(erased.len(), i) {
+                        erased.swap_remove(i);
+                    }
+                }
+                Op::Downcast(i) => {
+                    if let Some(i) = pick(erased.len(), i) {
+                        let cell = erased.swap_remove(i);
+                        cells.push(cell.downcast::<u64>().ok().unwrap());
+                    }
+                }
+                Op::DowncastWrong(i) => {
+                    if let Some(i) = pick(erased.len(), i) {
+                        assert!(erased[i].clone().downcast::<u32>().is_err());
+                    }
+                }
+            }
+        }
+    }};
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    run!(unsync, &ops);
+    run!(sync, &ops);
+});
@@ -0,0 +1,110 @@
+//! Runs arbitrary sequences of operations on slice and `str` cells of both
+//! flavours, whose length is stored in the header and read back on every
+//! access.
+//!
+//! Run with `cargo +nightly fuzz run slice_ops`, which builds with
+//! AddressSanitizer by default.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    FromBytes(Vec<u8>),
+    FromArray(u8),
+    FromStr(String),
+    Concat(u8, u8),
+    Clone(u8),
+    Drop(u8),
+    Write(u8, u8),
+    Leak(u8),
+    IntoVec(u8),
+}
+
+/// Picks an element of a collection of `len` elements from a fuzzed index.
+fn pick(len: usize, i: u8) -> Option<usize> {
+    (len > 0).then(|| i as usize % len)
+}
+
+macro_rules! run {
+    ($flavour:ident, $ops:expr) => {{
+        use thin_cell::$flavour::ThinCell;
+
+        let mut cells: Vec<ThinCell<[u8]>> = Vec::new();
+
+        for op in $ops {
+            match op {
+                Op::FromBytes(bytes) => {
+                    let cell = ThinCell::from(&bytes[..]);
+                    assert_eq!(*cell.borrow(), bytes[..]);
+                    cells.push(cell);
+                }
+                Op::FromArray(byte) => cells.push(ThinCell::new([*byte; 7]).unsize_slice()),
+                Op::FromStr(s) => {
+                    let cell = ThinCell::<str>::from(&s[..]);
+                    assert_eq!(&*cell.borrow(), s);
+                    assert_eq!(cell.into_string().ok().unwrap(), *s);
+                }
+                Op::Concat(a, b) => {
+                    if let (Some(a), Some(b)) = (pick(cells.len(), *a), pick(cells.len(), *b)) {
+                        let a = cells[a].borrow().to_vec();
+                        let b = cells[b].borrow().to_vec();
+                        let cell = ThinCell::<[u8]>::concat(&[&a, &b]);
+                        assert_eq!(cell.borrow().len(), a.len() + b.len());
+                        cells.push(cell);
+                    }
+                }
+                Op::Clone(i) => {
+                    if let Some(i) = pick(cells.len(), *i) {
+                        cells.push(cells[i].clone());
+                    }
+                }
+                Op::Drop(i) => {
+                    if let Some(i) = pick(cells.len(), *i) {
+                        cells.swap_remove(i);
+                    }
+                }
+                Op::Write(i, byte) => {
+                    if let Some(i) = pick(cells.len(), *i) {
+                        cells[i].borrow().fill(*byte);
+                    }
+                }
+                Op::Leak(i) => {
+                    if let Some(i) = pick(cells.len(), *i) {
+                        let cell = cells.swap_remove(i);
+                        let len = cell.borrow().len();
+                        let ptr = cell.leak();
+                        // SAFETY: `ptr` was just leaked from a `ThinCell<[u8]>`
+                        let cell = unsafe { ThinCell::<[u8]>::from_raw(ptr) };
+                        assert_eq!(cell.borrow().len(), len);
+                        cells.push(cell);
+                    }
+                }
+                Op::IntoVec(i) => {
+                    if let Some(i) = pick(cells.len(), *i) {
+                        let cell = cells.swap_remove(i);
+                        let unique = cell.count() == 1;
+                        let expected = cell.borrow().to_vec();
+                        match cell.into_vec() {
+                            Ok(vec) => {
+                                assert!(unique);
+                                assert_eq!(vec, expected);
+                            }
+                            Err(cell) => {
+                                assert!(!unique);
+                                cells.push(cell);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }};
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    run!(unsync, &ops);
+    run!(sync, &ops);
+});
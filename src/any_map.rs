@@ -0,0 +1,106 @@
+macro_rules! thin_any_map {
+    ($($bound:tt)+) => {
+        /// A map holding at most one `ThinCell` per type, e.g., for extension or
+        /// context registries.
+        ///
+        /// Values are stored as `ThinCell<dyn Any>` keyed by their [`TypeId`], and
+        /// handed out as typed handles of the same cell.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinAnyMap;
+        /// let mut map = ThinAnyMap::new();
+        /// map.insert(1u32);
+        /// map.insert(String::from("hello"));
+        ///
+        /// let cell = map.get::<u32>().unwrap();
+        /// *cell.borrow() += 1;
+        /// assert_eq!(*map.get::<u32>().unwrap().borrow(), 2);
+        /// assert!(map.get::<i32>().is_none());
+        /// ```
+        #[derive(Clone, Default)]
+        pub struct ThinAnyMap {
+            cells: HashMap<TypeId, ThinCell<dyn $($bound)+>>,
+        }
+
+        impl ThinAnyMap {
+            /// Creates an empty map.
+            pub fn new() -> Self {
+                ThinAnyMap {
+                    cells: HashMap::new(),
+                }
+            }
+
+            /// Inserts `value` in a new cell, returning the cell previously stored
+            /// for `T`, if any.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn insert<T: $($bound)+>(&mut self, value: T) -> Option<ThinCell<T>> {
+                self.insert_cell(ThinCell::new(value))
+            }
+
+            /// Stores a handle of an existing cell, returning the cell previously
+            /// stored for `T`, if any.
+            pub fn insert_cell<T: $($bound)+>(
+                &mut self,
+                cell: ThinCell<T>,
+            ) -> Option<ThinCell<T>> {
+                // SAFETY: unsized coercion from `T` to `dyn Any` is safe
+                let cell = unsafe { cell.unsize(|ptr| ptr as _) };
+                self.cells
+                    .insert(TypeId::of::<T>(), cell)
+                    // SAFETY: cells are keyed by the `TypeId` of their value
+                    .map(|cell| unsafe { cell.downcast_unchecked() })
+            }
+
+            /// Returns a handle of the cell stored for `T`.
+            ///
+            /// Unlike [`ThinCell::downcast`], this succeeds even if the cell is
+            /// currently borrowed.
+            pub fn get<T: $($bound)+>(&self) -> Option<ThinCell<T>> {
+                self.cells
+                    .get(&TypeId::of::<T>())
+                    // SAFETY: cells are keyed by the `TypeId` of their value
+                    .map(|cell| unsafe { cell.clone().downcast_unchecked() })
+            }
+
+            /// Removes the cell stored for `T` from the map and returns it.
+            pub fn remove<T: $($bound)+>(&mut self) -> Option<ThinCell<T>> {
+                self.cells
+                    .remove(&TypeId::of::<T>())
+                    // SAFETY: cells are keyed by the `TypeId` of their value
+                    .map(|cell| unsafe { cell.downcast_unchecked() })
+            }
+
+            /// Returns `true` if the map holds a cell for `T`.
+            pub fn contains<T: $($bound)+>(&self) -> bool {
+                self.cells.contains_key(&TypeId::of::<T>())
+            }
+
+            /// Returns the number of cells in the map.
+            pub fn len(&self) -> usize {
+                self.cells.len()
+            }
+
+            /// Returns `true` if the map holds no cells.
+            pub fn is_empty(&self) -> bool {
+                self.cells.is_empty()
+            }
+
+            /// Removes all cells from the map.
+            pub fn clear(&mut self) {
+                self.cells.clear();
+            }
+        }
+
+        impl Debug for ThinAnyMap {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("ThinAnyMap")
+                    .field("len", &self.len())
+                    .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_any_map;
@@ -0,0 +1,136 @@
+macro_rules! thin_cancel {
+    () => {
+        /// A cancellation token, whose clones all observe the same cancellation.
+        ///
+        /// The flag lives in the header of a [`HeaderCell`], so
+        /// [`is_cancelled`](ThinCancelToken::is_cancelled) never borrows, and the
+        /// value holds the wakers of the tasks waiting on
+        /// [`cancelled`](ThinCancelToken::cancelled). Each handle is one pointer
+        /// wide, which suits per-operation tokens.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinCancelToken;
+        /// let token = ThinCancelToken::new();
+        /// let op = token.clone();
+        /// assert!(!op.is_cancelled());
+        ///
+        /// token.cancel();
+        /// assert!(op.is_cancelled());
+        /// ```
+        pub struct ThinCancelToken {
+            cell: HeaderCell<std::sync::atomic::AtomicBool, Vec<std::task::Waker>>,
+        }
+
+        impl ThinCancelToken {
+            /// Creates a new token, not cancelled.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new() -> Self {
+                ThinCancelToken {
+                    cell: HeaderCell::new(std::sync::atomic::AtomicBool::new(false), Vec::new()),
+                }
+            }
+
+            /// Cancels the token, waking the tasks waiting on
+            /// [`cancelled`](ThinCancelToken::cancelled). Cancelling it again does
+            /// nothing.
+            pub fn cancel(&self) {
+                if self
+                    .cell
+                    .header()
+                    .swap(true, std::sync::atomic::Ordering::AcqRel)
+                {
+                    return;
+                }
+                // Wake outside of the borrow, as wakers may run arbitrary code
+                let wakers = std::mem::take(&mut *self.cell.borrow());
+                wakers.into_iter().for_each(std::task::Waker::wake);
+            }
+
+            /// Returns `true` if the token has been cancelled.
+            pub fn is_cancelled(&self) -> bool {
+                self.cell
+                    .header()
+                    .load(std::sync::atomic::Ordering::Acquire)
+            }
+
+            /// Waits until the token is cancelled.
+            pub fn cancelled(&self) -> Cancelled<'_> {
+                Cancelled { token: self }
+            }
+
+            /// Returns the number of handles of the token.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles belong to the same token.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl Clone for ThinCancelToken {
+            fn clone(&self) -> Self {
+                ThinCancelToken {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl Default for ThinCancelToken {
+            fn default() -> Self {
+                ThinCancelToken::new()
+            }
+        }
+
+        impl Debug for ThinCancelToken {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("ThinCancelToken")
+                    .field("cancelled", &self.is_cancelled())
+                    .finish()
+            }
+        }
+
+        /// Future returned by [`ThinCancelToken::cancelled`].
+        #[must_use = "futures do nothing unless polled"]
+        pub struct Cancelled<'a> {
+            token: &'a ThinCancelToken,
+        }
+
+        impl std::future::Future for Cancelled<'_> {
+            type Output = ();
+
+            fn poll(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<()> {
+                if self.token.is_cancelled() {
+                    return std::task::Poll::Ready(());
+                }
+
+                let mut wakers = self.token.cell.borrow();
+                // Checked again under the borrow, which `cancel` takes after setting
+                // the flag, so the wakeup can't be missed.
+                if self.token.is_cancelled() {
+                    return std::task::Poll::Ready(());
+                }
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                std::task::Poll::Pending
+            }
+        }
+
+        impl Debug for Cancelled<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Cancelled")
+                    .field("token", self.token)
+                    .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_cancel;
@@ -0,0 +1,129 @@
+macro_rules! thin_cow {
+    () => {
+        /// A value that is either borrowed for `'a` or owned in a [`ThinCell`],
+        /// like [`Cow`](std::borrow::Cow) without the `ToOwned` buffer.
+        ///
+        /// Owned values are one pointer wide, so a `ThinCow<str>` is as large as a
+        /// `&str`, a word smaller than `Cow<str>`. It suits parsers that mostly
+        /// borrow their input but occasionally need to own an edited copy.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinCow;
+        /// let mut cow = ThinCow::Borrowed("hello");
+        /// assert!(cow.is_borrowed());
+        ///
+        /// cow.to_mut().make_ascii_uppercase();
+        /// assert!(cow.is_owned());
+        /// assert_eq!(cow.to_string(), "HELLO");
+        /// ```
+        pub enum ThinCow<'a, T: ?Sized> {
+            /// A borrowed value.
+            Borrowed(&'a T),
+            /// A value owned in a cell, possibly shared with other handles.
+            Owned(ThinCell<T>),
+        }
+
+        impl<'a, T: ?Sized> ThinCow<'a, T> {
+            /// Returns `true` if the value is borrowed.
+            pub fn is_borrowed(&self) -> bool {
+                matches!(self, ThinCow::Borrowed(_))
+            }
+
+            /// Returns `true` if the value is owned in a cell.
+            pub fn is_owned(&self) -> bool {
+                matches!(self, ThinCow::Owned(_))
+            }
+
+            /// Borrows the value mutably, copying it into a new cell first if it is
+            /// borrowed.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn to_mut(&mut self) -> Ref<'_, T>
+            where
+                ThinCell<T>: From<&'a T>,
+            {
+                if let ThinCow::Borrowed(value) = *self {
+                    *self = ThinCow::Owned(ThinCell::from(value));
+                }
+                match self {
+                    ThinCow::Owned(cell) => cell.borrow(),
+                    ThinCow::Borrowed(_) => unreachable!(),
+                }
+            }
+
+            /// Returns the owned cell, copying the value into a new one if it is
+            /// borrowed.
+            pub fn into_owned(self) -> ThinCell<T>
+            where
+                ThinCell<T>: From<&'a T>,
+            {
+                match self {
+                    ThinCow::Borrowed(value) => ThinCell::from(value),
+                    ThinCow::Owned(cell) => cell,
+                }
+            }
+
+            /// Calls `f` with a reference to the value.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+                match self {
+                    ThinCow::Borrowed(value) => f(value),
+                    ThinCow::Owned(cell) => f(&cell.borrow()),
+                }
+            }
+        }
+
+        impl<'a, T: ?Sized> Clone for ThinCow<'a, T> {
+            /// Clones the reference or the cell handle, sharing an owned value.
+            fn clone(&self) -> Self {
+                match self {
+                    ThinCow::Borrowed(value) => ThinCow::Borrowed(*value),
+                    ThinCow::Owned(cell) => ThinCow::Owned(cell.clone()),
+                }
+            }
+        }
+
+        impl<'a, T: ?Sized> From<&'a T> for ThinCow<'a, T> {
+            fn from(value: &'a T) -> Self {
+                ThinCow::Borrowed(value)
+            }
+        }
+
+        impl<'a, T: ?Sized> From<ThinCell<T>> for ThinCow<'a, T> {
+            fn from(cell: ThinCell<T>) -> Self {
+                ThinCow::Owned(cell)
+            }
+        }
+
+        impl<'a, T: Debug + ?Sized> Debug for ThinCow<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    ThinCow::Borrowed(value) => f.debug_tuple("Borrowed").field(value).finish(),
+                    ThinCow::Owned(cell) => f.debug_tuple("Owned").field(cell).finish(),
+                }
+            }
+        }
+
+        impl<'a, T: Display + ?Sized> Display for ThinCow<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    ThinCow::Borrowed(value) => Display::fmt(value, f),
+                    ThinCow::Owned(cell) => Display::fmt(cell, f),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use thin_cow;
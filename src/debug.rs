@@ -0,0 +1,418 @@
+//! Debugging utilities to track down leaked cells, e.g., reference cycles or
+//! forgotten [`leak`](crate::unsync::ThinCell::leak)s.
+//!
+//! Every allocation made by `ThinCell` is recorded in a registry, together
+//! with the type of its value and where it was created, until it is freed.
+//!
+//! Only available with the `debug` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use thin_cell::{debug, unsync::ThinCell};
+//!
+//! let cell = ThinCell::new(42);
+//! assert_eq!(debug::live().len(), 1);
+//!
+//! drop(cell);
+//! debug::assert_no_leaks();
+//! ```
+//!
+//! Reference cycles can be found with [`find_cycles`], for values implementing
+//! [`Trace`].
+
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    fmt,
+    panic::Location,
+    sync::{Mutex, PoisonError},
+    thread::{self, ThreadId},
+};
+
+static REGISTRY: Mutex<BTreeMap<usize, LiveCell>> = Mutex::new(BTreeMap::new());
+
+/// A cell whose allocation has not been freed yet.
+#[derive(Debug, Clone)]
+pub struct LiveCell {
+    /// Address of the allocation, as returned by `as_ptr`.
+    pub addr: usize,
+    /// Type of the value at construction, before any unsizing.
+    pub type_name: &'static str,
+    /// Where the cell was constructed.
+    pub location: &'static Location<'static>,
+    /// Thread the cell was constructed on.
+    pub thread: ThreadId,
+}
+
+impl fmt::Display for LiveCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}: {} created at {}",
+            self.addr, self.type_name, self.location
+        )
+    }
+}
+
+pub(crate) fn register(ptr: *const (), type_name: &'static str, location: &'static Location) {
+    let cell = LiveCell {
+        addr: ptr.addr(),
+        type_name,
+        location,
+        thread: thread::current().id(),
+    };
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(cell.addr, cell);
+}
+
+pub(crate) fn unregister(ptr: *const ()) {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&ptr.addr());
+}
+
+/// Returns the live cells created on the current thread, ordered by address.
+///
+/// Cells created on other threads are left out, so that concurrently running
+/// tests don't see each other's cells.
+pub fn live() -> Vec<LiveCell> {
+    let thread = thread::current().id();
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .filter(|cell| cell.thread == thread)
+        .cloned()
+        .collect()
+}
+
+/// Prints the live cells created on the current thread to stderr.
+pub fn dump_live() {
+    let live = live();
+    eprintln!("{} live `ThinCell` allocation(s)", live.len());
+    for cell in live {
+        eprintln!("  {cell}");
+    }
+}
+
+/// Asserts that all cells created on the current thread have been freed.
+///
+/// # Panics
+///
+/// Panics with the list of live cells if there are any.
+#[track_caller]
+pub fn assert_no_leaks() {
+    let live = live();
+    if !live.is_empty() {
+        let list = live
+            .iter()
+            .map(|cell| format!("\n  {cell}"))
+            .collect::<String>();
+        panic!("{} `ThinCell` allocation(s) leaked:{list}", live.len());
+    }
+}
+
+/// Types that can report the `ThinCell`s they hold, so that [`find_cycles`]
+/// can walk the graph of cells.
+///
+/// Implementations call [`Trace::trace`] on every field that may (indirectly)
+/// hold a cell; `ThinCell` itself forwards to its value.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{
+///     debug::{Trace, Tracer},
+///     unsync::ThinCell,
+/// };
+///
+/// struct Node {
+///     next: Option<ThinCell<Node>>,
+/// }
+///
+/// impl Trace for Node {
+///     fn trace(&self, tracer: &mut Tracer) {
+///         self.next.trace(tracer);
+///     }
+/// }
+/// ```
+pub trait Trace {
+    /// Reports the cells held by `self` to `tracer`.
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+/// Collects the edges between cells while tracing, see [`Trace`].
+#[derive(Debug, Default)]
+pub struct Tracer {
+    /// Reachable cells by address, with their count and outgoing edges.
+    nodes: BTreeMap<usize, Node>,
+    /// The cell whose value is being traced.
+    current: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Node {
+    count: usize,
+    edges: Vec<usize>,
+}
+
+impl Tracer {
+    /// Records an edge to the cell at `addr` and returns whether it was seen
+    /// for the first time, in which case its value should be traced with
+    /// [`Tracer::enter`].
+    #[doc(hidden)]
+    pub fn visit(&mut self, addr: usize, count: usize) -> bool {
+        if let Some(from) = self.current {
+            self.nodes.get_mut(&from).unwrap().edges.push(addr);
+        }
+        match self.nodes.entry(addr) {
+            Entry::Vacant(entry) => {
+                entry.insert(Node {
+                    count,
+                    edges: Vec::new(),
+                });
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Traces `value` as the value of the cell at `addr`.
+    #[doc(hidden)]
+    pub fn enter(&mut self, addr: usize, value: &(impl Trace + ?Sized)) {
+        let parent = self.current.replace(addr);
+        value.trace(self);
+        self.current = parent;
+    }
+
+    /// Splits the traced graph into strongly connected components using
+    /// Tarjan's algorithm, keeping those that form a cycle.
+    fn cycles(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'a> {
+            nodes: &'a BTreeMap<usize, Node>,
+            index: BTreeMap<usize, (usize, usize)>,
+            stack: Vec<usize>,
+            cycles: Vec<Vec<usize>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, addr: usize) -> usize {
+                let index = self.index.len();
+                self.index.insert(addr, (index, index));
+                self.stack.push(addr);
+
+                let mut low = index;
+                for &next in &self.nodes[&addr].edges {
+                    let next_low = match self.index.get(&next) {
+                        None => self.visit(next),
+                        Some(&(next_index, _)) if self.stack.contains(&next) => next_index,
+                        Some(_) => continue,
+                    };
+                    low = low.min(next_low);
+                }
+                self.index.insert(addr, (index, low));
+
+                if low == index {
+                    let pos = self.stack.iter().rposition(|&a| a == addr).unwrap();
+                    let group = self.stack.split_off(pos);
+                    if group.len() > 1 || self.nodes[&addr].edges.contains(&addr) {
+                        self.cycles.push(group);
+                    }
+                }
+                low
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            nodes: &self.nodes,
+            index: BTreeMap::new(),
+            stack: Vec::new(),
+            cycles: Vec::new(),
+        };
+        for &addr in self.nodes.keys() {
+            if !tarjan.index.contains_key(&addr) {
+                tarjan.visit(addr);
+            }
+        }
+        tarjan.cycles
+    }
+}
+
+/// A group of cells referencing each other, found by [`find_cycles`].
+#[derive(Debug, Clone)]
+pub struct Cycle {
+    /// The cells in the cycle, ordered by address.
+    pub cells: Vec<LiveCell>,
+    /// Number of handles to the cells in the cycle held from outside of it.
+    ///
+    /// Once this drops to zero, e.g. when the handles passed to [`find_cycles`]
+    /// are gone, the cycle is leaked.
+    pub external: usize,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cycle of {} cell(s) with {} external handle(s):",
+            self.cells.len(),
+            self.external
+        )?;
+        for cell in &self.cells {
+            write!(f, "\n  {cell}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Walks the cells reachable from `root` and returns the reference cycles
+/// among them.
+///
+/// Cells that are borrowed during the walk are reported but not traced
+/// further.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{
+///     debug::{self, Trace, Tracer},
+///     unsync::ThinCell,
+/// };
+///
+/// struct Node {
+///     next: Option<ThinCell<Node>>,
+/// }
+///
+/// impl Trace for Node {
+///     fn trace(&self, tracer: &mut Tracer) {
+///         self.next.trace(tracer);
+///     }
+/// }
+///
+/// let a = ThinCell::new(Node { next: None });
+/// let b = ThinCell::new(Node {
+///     next: Some(a.clone()),
+/// });
+/// a.borrow().next = Some(b.clone());
+///
+/// let cycles = debug::find_cycles(&a);
+/// assert_eq!(cycles.len(), 1);
+/// assert_eq!(cycles[0].cells.len(), 2);
+/// // Held by `a` and `b`
+/// assert_eq!(cycles[0].external, 2);
+///
+/// // Break the cycle
+/// a.borrow().next = None;
+/// assert!(debug::find_cycles(&a).is_empty());
+/// ```
+pub fn find_cycles(root: &(impl Trace + ?Sized)) -> Vec<Cycle> {
+    let mut tracer = Tracer::default();
+    root.trace(&mut tracer);
+
+    let registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    tracer
+        .cycles()
+        .into_iter()
+        .map(|mut group| {
+            group.sort_unstable();
+            let count = group
+                .iter()
+                .map(|addr| tracer.nodes[addr].count)
+                .sum::<usize>();
+            let internal = group
+                .iter()
+                .flat_map(|addr| &tracer.nodes[addr].edges)
+                .filter(|addr| group.binary_search(addr).is_ok())
+                .count();
+            Cycle {
+                cells: group.iter().map(|addr| registry[addr].clone()).collect(),
+                external: count.saturating_sub(internal),
+            }
+        })
+        .collect()
+}
+
+macro_rules! impl_trace_noop {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Trace for $ty {
+                fn trace(&self, _: &mut Tracer) {}
+            }
+        )*
+    };
+}
+
+impl_trace_noop!(
+    (),
+    bool,
+    char,
+    str,
+    String,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+);
+
+impl<T: Trace + ?Sized> Trace for &T {
+    fn trace(&self, tracer: &mut Tracer) {
+        (**self).trace(tracer)
+    }
+}
+
+impl<T: Trace + ?Sized> Trace for Box<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        (**self).trace(tracer)
+    }
+}
+
+impl<T: Trace> Trace for Option<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(value) = self {
+            value.trace(tracer)
+        }
+    }
+}
+
+impl<T: Trace> Trace for [T] {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.iter().for_each(|value| value.trace(tracer))
+    }
+}
+
+impl<T: Trace, const N: usize> Trace for [T; N] {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.as_slice().trace(tracer)
+    }
+}
+
+impl<T: Trace> Trace for Vec<T> {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.as_slice().trace(tracer)
+    }
+}
+
+impl<K, V: Trace> Trace for BTreeMap<K, V> {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.values().for_each(|value| value.trace(tracer))
+    }
+}
+
+impl<K, V: Trace, S> Trace for std::collections::HashMap<K, V, S> {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.values().for_each(|value| value.trace(tracer))
+    }
+}
@@ -0,0 +1,70 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+/// A leaked last handle, dropped by `drop` when taken out of the queue.
+struct Deferred {
+    ptr: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` was leaked from the handle `drop` restores
+        unsafe { (self.drop)(self.ptr) }
+    }
+}
+
+thread_local! {
+    static QUEUE: RefCell<VecDeque<Deferred>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Queues a leaked last handle on the current thread, or drops it right away
+/// if the thread is exiting.
+///
+/// # Safety
+///
+/// `drop` must restore and drop the handle leaked as `ptr`.
+pub(crate) unsafe fn push(ptr: *mut (), drop: unsafe fn(*mut ())) {
+    let deferred = Deferred { ptr, drop };
+    // If the queue was destroyed as the thread exits, `deferred` is dropped
+    // along with the closure instead
+    let _ = QUEUE.try_with(|queue| queue.borrow_mut().push_back(deferred));
+}
+
+/// Drops up to `budget` values queued by `ThinCell::drop_deferred` on the
+/// current thread, oldest first, and returns how many were dropped.
+///
+/// Values queued while draining, e.g. by the drop of a queued value, are
+/// dropped by the same call if the budget allows.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{deferred_len, drain_deferred, unsync::ThinCell};
+///
+/// ThinCell::new(vec![0u8; 1 << 20]).drop_deferred();
+/// ThinCell::new(vec![1u8; 1 << 20]).drop_deferred();
+/// assert_eq!(deferred_len(), 2);
+///
+/// // e.g. at the end of an event loop turn
+/// assert_eq!(drain_deferred(1), 1);
+/// assert_eq!(drain_deferred(usize::MAX), 1);
+/// assert_eq!(deferred_len(), 0);
+/// ```
+pub fn drain_deferred(budget: usize) -> usize {
+    let mut dropped = 0;
+    while dropped < budget {
+        // Release the queue before dropping, which may queue more values
+        let Some(deferred) = QUEUE.with(|queue| queue.borrow_mut().pop_front()) else {
+            break;
+        };
+        drop(deferred);
+        dropped += 1;
+    }
+    dropped
+}
+
+/// Returns the number of values queued by `ThinCell::drop_deferred` on the
+/// current thread and not dropped yet.
+pub fn deferred_len() -> usize {
+    QUEUE.with(|queue| queue.borrow().len())
+}
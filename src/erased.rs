@@ -0,0 +1,127 @@
+macro_rules! thin_erased {
+    ($($bound:tt)*) => {
+        /// Object safe trait implemented by every type, used to erase the value
+        /// of an [`ErasedThinCell`] while keeping its drop glue and layout.
+        trait Erased {}
+
+        impl<T: ?Sized> Erased for T {}
+
+        /// A type-erased cell holding any `'static` value, whose [`TypeId`] is
+        /// recorded in the header at construction.
+        ///
+        /// Unlike `ThinCell<dyn Any>`, checking the type is a comparison with the
+        /// header rather than a vtable call, and doesn't borrow the value. This
+        /// suits registries storing arbitrary user types, e.g. plugins.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ErasedThinCell;
+        /// let cell = ErasedThinCell::new(String::from("plugin"));
+        /// assert!(cell.is::<String>());
+        ///
+        /// cell.borrow::<String>().unwrap().push('!');
+        /// assert!(cell.borrow::<u32>().is_none());
+        ///
+        /// let typed = cell.downcast::<String>().unwrap();
+        /// assert_eq!(typed.try_unwrap().unwrap().1, "plugin!");
+        /// ```
+        #[derive(Clone)]
+        pub struct ErasedThinCell {
+            cell: HeaderCell<TypeId, dyn Erased $($bound)*>,
+        }
+
+        impl ErasedThinCell {
+            /// Creates a new cell holding `value`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new<T: 'static $($bound)*>(value: T) -> Self {
+                let HeaderCell { cell } = HeaderCell::new(TypeId::of::<T>(), value);
+                ErasedThinCell {
+                    // SAFETY: unsized coercion from `T` to `dyn Erased` is safe
+                    cell: HeaderCell {
+                        cell: unsafe { cell.unsize(|ptr| ptr as _) },
+                    },
+                }
+            }
+
+            /// Returns the [`TypeId`] of the value.
+            pub fn type_id(&self) -> TypeId {
+                *self.cell.header()
+            }
+
+            /// Returns `true` if the value is of type `T`.
+            pub fn is<T: 'static>(&self) -> bool {
+                self.type_id() == TypeId::of::<T>()
+            }
+
+            /// Borrows the value mutably as a `T`, returning `None` if it is of
+            /// another type.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the value is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn borrow<T: 'static>(&self) -> Option<Ref<'_, T>> {
+                if !self.is::<T>() {
+                    return None;
+                }
+
+                // SAFETY: the value is a `T`, as recorded in the header
+                Some(Ref::map(self.cell.borrow(), |value| unsafe {
+                    &mut *(&raw mut *value).cast::<T>()
+                }))
+            }
+
+            /// Tries to borrow the value mutably as a `T`, returning `None` if it is
+            /// of another type or already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow<T: 'static>(&self) -> Option<Ref<'_, T>> {
+                if !self.is::<T>() {
+                    return None;
+                }
+
+                // SAFETY: the value is a `T`, as recorded in the header
+                Some(Ref::map(self.cell.try_borrow()?, |value| unsafe {
+                    &mut *(&raw mut *value).cast::<T>()
+                }))
+            }
+
+            /// Returns a typed handle of the cell, or `Err(self)` if the value is of
+            /// another type.
+            ///
+            /// Unlike [`ThinCell::downcast`], this succeeds even if the cell is
+            /// currently borrowed or shared.
+            pub fn downcast<T: 'static>(self) -> Result<HeaderCell<TypeId, T>, Self> {
+                if !self.is::<T>() {
+                    return Err(self);
+                }
+
+                // SAFETY: the value is a `T`, as recorded in the header
+                Ok(HeaderCell {
+                    cell: unsafe { self.cell.cell.downcast_unchecked() },
+                })
+            }
+
+            /// Returns the number of owners.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles point to the same allocation.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl Debug for ErasedThinCell {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("ErasedThinCell")
+                    .field("type_id", &self.type_id())
+                    .finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_erased;
@@ -0,0 +1,91 @@
+macro_rules! thin_error {
+    ($($bound:tt)*) => {
+        /// A cheaply cloned, shared error value, which itself implements
+        /// [`Error`](std::error::Error).
+        ///
+        /// The error is stored in a `ThinCell<dyn Error>` and never borrowed
+        /// mutably once wrapped, so it can be displayed and walked through
+        /// [`source`](std::error::Error::source) from any handle. Being one
+        /// pointer wide, it is cheap to pass through `?` and to wrap in other
+        /// error types.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinError;
+        /// use std::error::Error;
+        ///
+        /// let err = ThinError::new(std::fmt::Error);
+        /// let other = err.clone();
+        ///
+        /// assert_eq!(other.to_string(), "an error occurred when formatting an argument");
+        /// assert!(err.downcast_ref::<std::fmt::Error>().is_some());
+        /// assert!(err.source().is_none());
+        /// ```
+        #[derive(Clone)]
+        pub struct ThinError {
+            cell: ThinCell<dyn std::error::Error $($bound)*>,
+        }
+
+        impl ThinError {
+            /// Creates a new shared error holding `error`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new<E: std::error::Error $($bound)* + 'static>(error: E) -> Self {
+                ThinError {
+                    // SAFETY: unsized coercion from `E` to `dyn Error` is safe
+                    cell: unsafe { ThinCell::new_unsize(error, |ptr| ptr as _) },
+                }
+            }
+
+            /// Returns a reference to the wrapped error.
+            pub fn get(&self) -> &(dyn std::error::Error $($bound)* + 'static) {
+                // SAFETY: The cell is only reachable through `ThinError` handles,
+                // which never borrow it.
+                unsafe { &*self.cell.inner().data.get() }
+            }
+
+            /// Returns a reference to the wrapped error if it is of type `E`.
+            pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+                self.get().downcast_ref()
+            }
+
+            /// Returns `true` if the two handles point to the same error.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl Debug for ThinError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(self.get(), f)
+            }
+        }
+
+        impl Display for ThinError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Display::fmt(self.get(), f)
+            }
+        }
+
+        impl std::error::Error for ThinError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                self.get().source()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_error;
+
+/// The error returned by fallible constructors like `ThinCell::try_new` when
+/// the allocation fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl std::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl std::error::Error for AllocError {}
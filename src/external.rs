@@ -0,0 +1,121 @@
+//! Support for cells living in allocations not owned by a `Box`.
+//!
+//! Such cells have [`EXTERNAL_MASK`](crate::state::EXTERNAL_MASK) set in their
+//! state, and a pointer to a [`Release`] header stored in the word right before
+//! their `Inner`. Once the last owner is gone and the value has been dropped or
+//! moved out, the header is asked to reclaim the memory.
+
+use std::{alloc::Layout, ptr::NonNull};
+
+/// Header of an allocation that hands out external cells.
+///
+/// Implementors embed this as the first field of a `repr(C)` struct, so the
+/// pointer can be cast back to the concrete type in `release`.
+#[repr(C)]
+pub struct Release {
+    /// Reclaims the memory of the cell whose `Inner` is at the second argument.
+    pub release: unsafe fn(NonNull<Release>, NonNull<()>),
+}
+
+/// Layout of an allocation made of a header `H`, followed by `n` slots of `I`,
+/// each prefixed with a pointer to the [`Release`] header.
+pub struct SlotsLayout {
+    /// Layout of the whole allocation.
+    pub layout: Layout,
+    /// Offset of the first `I`.
+    pub first: usize,
+    /// Distance between two consecutive `I`s.
+    pub stride: usize,
+}
+
+impl SlotsLayout {
+    /// # Panics
+    ///
+    /// Panics if `n` is zero or the total size overflows.
+    pub fn new<H, I>(n: usize) -> Self {
+        let prefix = Layout::new::<NonNull<Release>>();
+        let item = Layout::new::<I>();
+
+        assert!(n > 0, "Cannot allocate zero slots");
+        // `Inner` always starts with `metadata: usize`, so the prefix word right
+        // before an item is always properly aligned.
+        debug_assert!(item.align() >= prefix.align());
+
+        let stride = (prefix.size() + item.size()).next_multiple_of(item.align());
+        let first = (size_of::<H>() + prefix.size()).next_multiple_of(item.align());
+        let size = stride
+            .checked_mul(n - 1)
+            .and_then(|size| size.checked_add(first + item.size()))
+            .expect("capacity overflow");
+        let layout = Layout::from_size_align(size, align_of::<H>().max(item.align()))
+            .expect("capacity overflow");
+
+        Self {
+            layout,
+            first,
+            stride,
+        }
+    }
+}
+
+/// Layout of an allocation made of a header `H`, followed by an `A` and a `B`,
+/// each prefixed with a pointer to the [`Release`] header.
+pub struct PairLayout {
+    /// Layout of the whole allocation.
+    pub layout: Layout,
+    /// Offset of the `A`.
+    pub first: usize,
+    /// Offset of the `B`.
+    pub second: usize,
+}
+
+impl PairLayout {
+    /// # Panics
+    ///
+    /// Panics if the total size overflows.
+    pub fn new<H, A, B>() -> Self {
+        /// Appends `item` to `layout` with room for the prefix right before it,
+        /// returning the new layout and the offset of `item`.
+        fn push(layout: Layout, item: Layout) -> (Layout, usize) {
+            let offset = (layout.size() + size_of::<NonNull<Release>>())
+                .checked_next_multiple_of(item.align())
+                .expect("capacity overflow");
+            let align = layout.align().max(item.align());
+            let layout = offset
+                .checked_add(item.size())
+                .and_then(|size| Layout::from_size_align(size, align).ok())
+                .expect("capacity overflow");
+            (layout, offset)
+        }
+
+        let (layout, first) = push(Layout::new::<H>(), Layout::new::<A>());
+        let (layout, second) = push(layout, Layout::new::<B>());
+        Self {
+            layout,
+            first,
+            second,
+        }
+    }
+}
+
+/// Writes the pointer to the [`Release`] header in front of an item.
+///
+/// # Safety
+///
+/// `inner` must be a slot laid out by [`SlotsLayout`] or [`PairLayout`].
+pub unsafe fn set_header(inner: NonNull<()>, header: NonNull<Release>) {
+    unsafe { inner.cast::<NonNull<Release>>().sub(1).write(header) }
+}
+
+/// Hands the memory of an external cell back to its [`Release`] header.
+///
+/// # Safety
+///
+/// `inner` must point to the `Inner` of an external cell, with its last owner
+/// gone and its value dropped or moved out.
+pub unsafe fn release(inner: NonNull<()>) {
+    unsafe {
+        let header = inner.cast::<NonNull<Release>>().sub(1).read();
+        (header.as_ref().release)(header, inner)
+    }
+}
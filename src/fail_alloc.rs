@@ -0,0 +1,46 @@
+//! Injects allocation failures into fallible constructors, to test how code
+//! handles running out of memory.
+//!
+//! After [`fail_next`], the next allocations of fallible constructors like
+//! `ThinCell::try_new` on the current thread fail with
+//! [`AllocError`](crate::AllocError) without reaching the allocator. Other
+//! constructors are not affected, as they would abort the process.
+//!
+//! Only available with the `fail-alloc` feature, which is meant for tests.
+//!
+//! # Examples
+//!
+//! ```
+//! use thin_cell::{AllocError, fail_alloc, unsync::ThinCell};
+//!
+//! fail_alloc::fail_next(1);
+//! assert_eq!(ThinCell::try_new(1).err(), Some(AllocError));
+//! assert!(ThinCell::try_new(2).is_ok());
+//! ```
+
+use std::cell::Cell;
+
+thread_local! {
+    static PENDING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Makes the next `n` allocations of fallible constructors on this thread
+/// fail, replacing the failures still pending.
+pub fn fail_next(n: usize) {
+    PENDING.set(n);
+}
+
+/// Returns how many injected failures are still pending on this thread.
+pub fn pending() -> usize {
+    PENDING.get()
+}
+
+/// Consumes a pending failure, returning whether the allocation should fail.
+pub(crate) fn take() -> bool {
+    let pending = PENDING.get();
+    if pending == 0 {
+        return false;
+    }
+    PENDING.set(pending - 1);
+    true
+}
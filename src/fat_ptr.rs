@@ -39,3 +39,19 @@ impl FatPtr {
         unsafe { fat.ptr }
     }
 }
+
+/// Aborts unless the fat pointer `ptr` splits back into the components it was
+/// built from, checked with `--cfg thin_cell_validate`.
+#[cfg(thin_cell_validate)]
+pub fn validate_round_trip<T: ?Sized>(ptr: *const T, components: FatPtr) {
+    // Not `FatPtr::from_ptr`, whose compile-time check would also reject the
+    // thin `T`s of code paths that only run for fat ones.
+    assert!(!is_sized::<T>());
+    // SAFETY: `ptr` is fat, so both components are initialized
+    let split = unsafe { FatPtrUnion { ptr }.component };
+
+    if split.ptr != components.ptr || split.metadata != components.metadata {
+        eprintln!("`ThinCell` invariant violated: pointer metadata round-trip, aborting");
+        std::process::abort()
+    }
+}
@@ -0,0 +1,76 @@
+//! Thread-local cache of freed allocations, enabled by the `freelist` feature.
+//!
+//! Cells allocate and free their memory through [`alloc`] and [`dealloc`],
+//! which have the same contract as their `std::alloc` counterparts. Freed
+//! memory is kept per layout for the next allocation on the same thread, so
+//! cells created and dropped in quick succession skip the global allocator.
+//! The memory always comes from the global allocator, so it can be freed on
+//! another thread than the one it was allocated on.
+
+use std::{alloc::Layout, cell::RefCell, collections::HashMap, ptr::NonNull};
+
+/// Number of allocations kept per layout.
+const CAPACITY: usize = 64;
+
+struct Freelist(HashMap<Layout, Vec<NonNull<u8>>>);
+
+impl Drop for Freelist {
+    fn drop(&mut self) {
+        for (layout, ptrs) in self.0.drain() {
+            for ptr in ptrs {
+                // SAFETY: Cached memory was allocated with `layout` and is unused
+                unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) }
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FREELIST: RefCell<Freelist> = RefCell::new(Freelist(HashMap::new()));
+}
+
+/// Allocates memory for `layout`, reusing memory freed on this thread if
+/// possible.
+///
+/// # Safety
+///
+/// See [`std::alloc::alloc`].
+pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+    let cached = FREELIST
+        .try_with(|list| list.borrow_mut().0.get_mut(&layout).and_then(Vec::pop))
+        .ok()
+        .flatten();
+
+    match cached {
+        Some(ptr) => ptr.as_ptr(),
+        // SAFETY: guaranteed by caller
+        None => unsafe { std::alloc::alloc(layout) },
+    }
+}
+
+/// Frees memory, keeping it for the next allocation of the same layout on this
+/// thread unless enough is kept already.
+///
+/// # Safety
+///
+/// See [`std::alloc::dealloc`].
+pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    // Keeping fails once the thread-local has been destroyed
+    let kept = FREELIST
+        .try_with(|list| {
+            let mut list = list.borrow_mut();
+            let ptrs = list.0.entry(layout).or_default();
+            if ptrs.len() == CAPACITY {
+                return false;
+            }
+            // SAFETY: guaranteed by caller to be a live allocation
+            ptrs.push(unsafe { NonNull::new_unchecked(ptr) });
+            true
+        })
+        .unwrap_or(false);
+
+    if !kept {
+        // SAFETY: guaranteed by caller
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+}
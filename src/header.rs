@@ -0,0 +1,249 @@
+/// A reference count kept outside of Rust, e.g. by GObject (`g_object_ref`/
+/// `g_object_unref`) or COM (`AddRef`/`Release`) objects.
+///
+/// Used as the header of a [`ForeignCell`](crate::unsync::ForeignCell), of
+/// which every handle owns one foreign reference.
+pub trait ForeignRc {
+    /// Takes a new foreign reference.
+    fn add_ref(&self);
+
+    /// Gives up a foreign reference taken by [`add_ref`](ForeignRc::add_ref) or
+    /// owned by the initial handle.
+    fn release(&self);
+}
+
+macro_rules! header_cell {
+    () => {
+        /// Value of a [`HeaderCell`], laid out next to its header.
+        #[repr(C)]
+        struct WithHeader<H, T: ?Sized> {
+            header: H,
+            value: T,
+        }
+
+        /// A [`ThinCell`] with a small header `H` stored in the same allocation,
+        /// which can be read without borrowing the value.
+        ///
+        /// The header suits data describing the value that other owners need while
+        /// it is borrowed (flags, an operation id, ...), without a second allocation
+        /// or going through the borrow flag. It is immutable, use `Cell`s or atomics
+        /// to change it.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::HeaderCell;
+        /// use std::cell::Cell;
+        ///
+        /// let cell = HeaderCell::new(Cell::new(0u32), vec![1, 2]);
+        /// let other = cell.clone();
+        ///
+        /// let mut value = cell.borrow();
+        /// value.push(3);
+        /// other.header().set(value.len() as u32);
+        /// drop(value);
+        ///
+        /// assert_eq!(cell.header().get(), 3);
+        /// ```
+        pub struct HeaderCell<H, T: ?Sized> {
+            cell: ThinCell<WithHeader<H, T>>,
+        }
+
+        impl<H, T> HeaderCell<H, T> {
+            /// Creates a new cell holding `header` and `value`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new(header: H, value: T) -> Self {
+                HeaderCell {
+                    cell: ThinCell::new(WithHeader { header, value }),
+                }
+            }
+
+            /// Consumes the handle and returns the header and the value if there are
+            /// no other owners and it is not borrowed, or `Err(self)` otherwise.
+            pub fn try_unwrap(self) -> Result<(H, T), Self> {
+                self.cell
+                    .try_unwrap()
+                    .map(|WithHeader { header, value }| (header, value))
+                    .map_err(|cell| HeaderCell { cell })
+            }
+        }
+
+        impl<H, T: ?Sized> HeaderCell<H, T> {
+            /// Returns a reference to the header, which never borrows the value.
+            pub fn header(&self) -> &H {
+                let data = self.cell.inner().data.get();
+                // SAFETY: Guards only ever reference the value, never the whole
+                // `WithHeader`, and the header is never mutated.
+                unsafe { &*(&raw const (*data).header) }
+            }
+
+            /// Creates a guard of the value.
+            ///
+            /// # Safety
+            ///
+            /// The borrow flag must have just been set by the caller.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            unsafe fn guard(&self) -> Ref<'_, T> {
+                let inner = self.cell.inner();
+
+                #[cfg(feature = "track-borrow")]
+                inner.state.track(std::panic::Location::caller());
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = self.cell.addr(), "borrow");
+
+                Ref {
+                    // SAFETY: We have exclusive access to the value via borrow flag, and
+                    // it doesn't overlap with the header.
+                    value: unsafe { &mut *(&raw mut (*inner.data.get()).value) },
+                    state: &inner.state,
+                }
+            }
+
+            /// Borrows the value mutably.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the value is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn borrow(&self) -> Ref<'_, T> {
+                self.cell.state().borrow();
+
+                // SAFETY: The borrow flag was just set
+                unsafe { self.guard() }
+            }
+
+            /// Tries to borrow the value mutably, returning `None` if it is already
+            /// borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow(&self) -> Option<Ref<'_, T>> {
+                if !self.cell.state().try_borrow() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(addr = self.cell.addr(), "borrow conflict");
+                    return None;
+                }
+
+                // SAFETY: The borrow flag was just set
+                Some(unsafe { self.guard() })
+            }
+
+            /// Returns the number of owners.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles point to the same allocation.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl<H, T: ?Sized> Clone for HeaderCell<H, T> {
+            fn clone(&self) -> Self {
+                HeaderCell {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl<H: Default, T: Default> Default for HeaderCell<H, T> {
+            fn default() -> Self {
+                HeaderCell::new(H::default(), T::default())
+            }
+        }
+
+        impl<H: Debug, T: Debug + ?Sized> Debug for HeaderCell<H, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut d = f.debug_struct("HeaderCell");
+                d.field("header", self.header());
+                match self.try_borrow() {
+                    Some(borrowed) => d.field("value", &borrowed),
+                    None => d.field("value", &format_args!("<borrowed>")),
+                }
+                .finish()
+            }
+        }
+
+        /// A [`HeaderCell`] whose handles also own references of the foreign
+        /// object in its header, so it can act as the Rust-side handle of an
+        /// object refcounted by C code.
+        ///
+        /// Cloning a handle calls [`ForeignRc::add_ref`](crate::ForeignRc::add_ref)
+        /// and dropping one calls [`ForeignRc::release`](crate::ForeignRc::release),
+        /// while the cell itself is freed once the last Rust handle is gone.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ForeignCell;
+        /// use std::{cell::Cell, rc::Rc};
+        ///
+        /// use thin_cell::ForeignRc;
+        ///
+        /// struct Object(Rc<Cell<u32>>);
+        ///
+        /// impl ForeignRc for Object {
+        ///     fn add_ref(&self) {
+        ///         self.0.set(self.0.get() + 1);
+        ///     }
+        ///
+        ///     fn release(&self) {
+        ///         self.0.set(self.0.get() - 1);
+        ///     }
+        /// }
+        ///
+        /// let refs = Rc::new(Cell::new(1));
+        /// let cell = ForeignCell::new(Object(refs.clone()), "state");
+        /// let other = cell.clone();
+        /// assert_eq!(refs.get(), 2);
+        ///
+        /// drop((cell, other));
+        /// assert_eq!(refs.get(), 0);
+        /// ```
+        pub struct ForeignCell<H: crate::ForeignRc, T: ?Sized> {
+            cell: HeaderCell<H, T>,
+        }
+
+        impl<H: crate::ForeignRc, T> ForeignCell<H, T> {
+            /// Creates a new cell holding `header` and `value`, taking over one
+            /// foreign reference owned by the caller.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new(header: H, value: T) -> Self {
+                ForeignCell {
+                    cell: HeaderCell::new(header, value),
+                }
+            }
+        }
+
+        impl<H: crate::ForeignRc, T: ?Sized> Deref for ForeignCell<H, T> {
+            type Target = HeaderCell<H, T>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.cell
+            }
+        }
+
+        impl<H: crate::ForeignRc, T: ?Sized> Clone for ForeignCell<H, T> {
+            fn clone(&self) -> Self {
+                self.cell.header().add_ref();
+                ForeignCell {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl<H: crate::ForeignRc, T: ?Sized> Drop for ForeignCell<H, T> {
+            fn drop(&mut self) {
+                self.cell.header().release();
+            }
+        }
+
+        impl<H: crate::ForeignRc + Debug, T: Debug + ?Sized> Debug for ForeignCell<H, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&self.cell, f)
+            }
+        }
+    };
+}
+
+pub(crate) use header_cell;
@@ -0,0 +1,99 @@
+macro_rules! thin_interner {
+    () => {
+        /// Deduplicates strings into shared `ThinCell<str>`s.
+        ///
+        /// Interning the same contents twice returns handles of the same cell, so
+        /// repeated strings (header names, identifiers) share one allocation and
+        /// can be compared with [`ThinCell::ptr_eq`].
+        ///
+        /// Strings that are no longer used outside of the interner are freed the
+        /// next time a string with the same hash is interned, or by
+        /// [`Interner::purge`]. A string that is mutated through its cell, or is
+        /// borrowed while interning, is not matched anymore.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::Interner;
+        /// let interner = Interner::new();
+        ///
+        /// let a = interner.intern("content-type");
+        /// let b = interner.intern(&String::from("content-type"));
+        /// assert!(a.ptr_eq(&b));
+        ///
+        /// drop((a, b));
+        /// interner.purge();
+        /// assert!(interner.is_empty());
+        /// ```
+        pub struct Interner {
+            buckets: Mutex<HashMap<u64, Vec<ThinCell<str>>>>,
+            hasher: RandomState,
+        }
+
+        impl Interner {
+            /// Creates an empty interner.
+            pub fn new() -> Self {
+                Interner {
+                    buckets: Mutex::new(HashMap::new()),
+                    hasher: RandomState::new(),
+                }
+            }
+
+            /// Returns the cell holding `s`, creating it if there is none.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn intern(&self, s: &str) -> ThinCell<str> {
+                let mut buckets = self.buckets.lock();
+                let bucket = buckets.entry(self.hasher.hash_one(s)).or_default();
+
+                if let Some(cell) = bucket
+                    .iter()
+                    .find(|cell| cell.try_borrow().is_some_and(|value| *value == *s))
+                {
+                    return cell.clone();
+                }
+
+                // Only the interner hands out new handles, so a count of one can't
+                // change concurrently.
+                bucket.retain(|cell| cell.count() > 1);
+                let cell = ThinCell::from(s);
+                bucket.push(cell.clone());
+                cell
+            }
+
+            /// Frees the strings that are only held by the interner.
+            pub fn purge(&self) {
+                self.buckets.lock().retain(|_, bucket| {
+                    bucket.retain(|cell| cell.count() > 1);
+                    !bucket.is_empty()
+                });
+            }
+
+            /// Returns the number of strings held by the interner, including those
+            /// not used anywhere else that have not been purged yet.
+            pub fn len(&self) -> usize {
+                self.buckets.lock().values().map(Vec::len).sum()
+            }
+
+            /// Returns `true` if the interner holds no strings.
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+        }
+
+        impl Default for Interner {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Debug for Interner {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Interner")
+                    .field("len", &self.len())
+                    .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_interner;
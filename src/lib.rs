@@ -1,11 +1,51 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
+#![cfg_attr(feature = "nightly", feature(dropck_eyepatch))]
 
 mod state;
 
+mod any_map;
+mod cancel;
+mod cow;
+mod deferred;
+mod erased;
+mod error;
+mod external;
 mod fat_ptr;
-
+#[cfg(feature = "freelist")]
+mod freelist;
+mod header;
+mod interner;
+mod literal;
+mod local;
+mod once;
+mod pool;
+mod project;
+mod registry;
+#[cfg(feature = "erased-serde")]
+mod serde_any;
+mod slab;
+mod slice;
+mod task;
+
+#[cfg(feature = "debug")]
+pub mod debug;
+#[cfg(feature = "fail-alloc")]
+pub mod fail_alloc;
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "track-borrow")]
+mod track;
+pub use deferred::{deferred_len, drain_deferred};
+pub use error::AllocError;
+pub use header::ForeignRc;
+#[cfg(feature = "stats")]
+pub use stats::{Stats, stats};
+
+pub mod raw;
 pub mod sync;
 pub mod unsync;
 
@@ -13,17 +53,28 @@ macro_rules! thin_cell {
     {
         $( #[$doc:meta] )*
     } => {
+        #[cfg(not(feature = "freelist"))]
+        use std::alloc::{alloc, dealloc};
+        #[cfg(feature = "futures-io")]
+        use std::{
+            pin::Pin,
+            task::{Context, Poll},
+        };
         use std::{
+            alloc::{Layout, handle_alloc_error},
             any::{Any, TypeId},
             cell::UnsafeCell,
             fmt::{self, Debug, Display},
             marker::PhantomData,
-            mem::ManuallyDrop,
+            mem::{ManuallyDrop, MaybeUninit},
             ops::{Deref, DerefMut},
+            panic::RefUnwindSafe,
             ptr::NonNull,
         };
 
-        use crate::fat_ptr::*;
+        #[cfg(feature = "freelist")]
+        use crate::freelist::{alloc, dealloc};
+        use crate::{AllocError, external::*, fat_ptr::*};
 
         /// The inner allocation of `ThinCell`
         ///
@@ -34,13 +85,104 @@ macro_rules! thin_cell {
             // points to the metadata
             metadata: usize,
             state: State,
-            data: UnsafeCell<T>,
+            pub(crate) data: UnsafeCell<T>,
+        }
+
+        /// Shared allocation holding the cells created by [`ThinCell::new_many`] and
+        /// [`ThinCell::new_pair`].
+        #[repr(C)]
+        struct Block {
+            header: Release,
+            /// Number of cells alive in this block.
+            state: State,
+            layout: Layout,
+        }
+
+        impl Block {
+            unsafe fn release(header: NonNull<Release>, _: NonNull<()>) {
+                let block = header.cast::<Block>();
+
+                // SAFETY: `header` is the first field of a live `Block`
+                let layout = {
+                    let block = unsafe { block.as_ref() };
+                    if !block.state.dec() {
+                        return;
+                    }
+                    block.layout
+                };
+
+                // SAFETY: The last cell in the block is gone
+                unsafe { dealloc(block.as_ptr().cast(), layout) }
+            }
+        }
+
+        /// Allocation holding a single cell with an over-aligned value, created by
+        /// [`ThinCell::new_aligned`] and [`ThinCell::new_aligned_zeroed`].
+        #[repr(C)]
+        struct Aligned {
+            header: Release,
+            layout: Layout,
+        }
+
+        impl Aligned {
+            unsafe fn release(header: NonNull<Release>, _: NonNull<()>) {
+                // SAFETY: `header` is the first field of a live `Aligned`
+                let layout = unsafe { header.cast::<Aligned>().as_ref().layout };
+
+                // SAFETY: The cell is gone
+                unsafe { dealloc(header.as_ptr().cast(), layout) }
+            }
+
+            /// Allocates an `Inner` of `size` bytes, whose value at `offset` is
+            /// aligned to `align`, and returns a pointer to it.
+            ///
+            /// `align` must be at least the alignment of the `Inner`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `align` is not a power of two or the size overflows.
+            fn alloc(size: usize, offset: usize, align: usize) -> NonNull<()> {
+                assert!(align.is_power_of_two(), "`align` must be a power of two");
+
+                let align = align.max(align_of::<Aligned>());
+                // Leave room for the header and the pointer to it right before `Inner`
+                let start = (size_of::<Aligned>() + size_of::<NonNull<Release>>() + offset)
+                    .checked_next_multiple_of(align)
+                    .expect("capacity overflow")
+                    - offset;
+                let layout = start
+                    .checked_add(size)
+                    .and_then(|size| Layout::from_size_align(size, align).ok())
+                    .expect("capacity overflow");
+
+                // SAFETY: `layout` has non-zero size
+                let Some(header) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+                let header = header.cast::<Aligned>();
+
+                // SAFETY: `header` is a fresh allocation with room for `Aligned`, and
+                // the `Inner` at `start` with its prefix word.
+                unsafe {
+                    header.write(Aligned {
+                        header: Release {
+                            release: Aligned::release,
+                        },
+                        layout,
+                    });
+                    let inner = header.cast::<u8>().add(start).cast::<()>();
+                    set_header(inner, header.cast());
+                    inner
+                }
+            }
         }
 
         $( #[$doc] )*
         pub struct ThinCell<T: ?Sized> {
             ptr: NonNull<()>,
-            _marker: PhantomData<Inner<T>>,
+            // `&mut ()` opts out of `UnwindSafe`, as the value can be mutated through
+            // any handle and borrows don't poison, see "Unwind Safety" in the docs.
+            _marker: PhantomData<(&'static mut (), Inner<T>)>,
         }
 
         /// A mutable guard returned by [`ThinCell::borrow`]
@@ -49,21 +191,334 @@ macro_rules! thin_cell {
             state: &'a State,
         }
 
+        // A shared `Ref` only gives out `&T`
+        impl<T: RefUnwindSafe + ?Sized> RefUnwindSafe for Ref<'_, T> {}
+
         impl<T> ThinCell<T> {
             /// Creates a new `ThinCell` wrapping the given data.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
             pub fn new(data: T) -> Self {
-                let alloc = Box::new(Inner {
-                    metadata: 0,
-                    state: State::new(),
-                    data: UnsafeCell::new(data),
-                });
+                let layout = Layout::new::<Inner<T>>();
+                // SAFETY: `layout` has non-zero size
+                let Some(ptr) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+
+                // SAFETY: `ptr` is a fresh allocation of `Inner<T>`
+                unsafe { Self::init(ptr, data) }
+            }
+
+            /// Like [`ThinCell::new`], but returns an error instead of aborting if
+            /// the allocation fails. The value is dropped in that case.
+            ///
+            /// Failures can be injected in tests with the `fail-alloc` feature, see
+            /// `thin_cell::fail_alloc`.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::try_new(5).unwrap();
+            /// assert_eq!(*cell.borrow(), 5);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn try_new(data: T) -> Result<Self, AllocError> {
+                #[cfg(feature = "fail-alloc")]
+                if crate::fail_alloc::take() {
+                    return Err(AllocError);
+                }
 
-                let ptr = Box::into_raw(alloc);
+                let layout = Layout::new::<Inner<T>>();
+                // SAFETY: `layout` has non-zero size
+                let ptr = NonNull::new(unsafe { alloc(layout) }).ok_or(AllocError)?;
 
-                ThinCell {
-                    ptr: unsafe { NonNull::new_unchecked(ptr as _) },
+                // SAFETY: `ptr` is a fresh allocation of `Inner<T>`
+                Ok(unsafe { Self::init(ptr, data) })
+            }
+
+            /// Moves `data` into the fresh allocation `ptr` and registers the cell.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must be a fresh allocation of `Inner<T>`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            unsafe fn init(ptr: NonNull<u8>, data: T) -> Self {
+                // SAFETY: Guaranteed by caller
+                unsafe {
+                    ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new(),
+                        data: UnsafeCell::new(data),
+                    })
+                };
+
+                let this = ThinCell {
+                    ptr: ptr.cast(),
+                    _marker: PhantomData,
+                };
+                this.register();
+                this
+            }
+
+            /// Creates a new `ThinCell` whose value is aligned to at least `align`
+            /// bytes, e.g. for buffers used with direct I/O.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `align` is not a power of two.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new_aligned([0u8; 512], 4096);
+            /// assert_eq!(cell.borrow().as_ptr().addr() % 4096, 0);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new_aligned(data: T, align: usize) -> Self {
+                let ptr = Aligned::alloc(
+                    size_of::<Inner<T>>(),
+                    std::mem::offset_of!(Inner<T>, data),
+                    align.max(align_of::<Inner<T>>()),
+                );
+
+                // SAFETY: `ptr` is valid for writes of `Inner<T>`
+                unsafe {
+                    ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new_external(),
+                        data: UnsafeCell::new(data),
+                    })
+                };
+
+                let this = ThinCell {
+                    ptr,
+                    _marker: PhantomData,
+                };
+                this.register();
+                this
+            }
+
+            /// Creates a new `ThinCell` whose value is initialized in place by `f`,
+            /// so that large values are never built on the stack and moved into the
+            /// allocation.
+            ///
+            /// # Safety
+            ///
+            /// `f` must fully initialize the value before returning.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = unsafe {
+            ///     ThinCell::<[u8; 1 << 20]>::new_with(|buf| {
+            ///         buf.as_mut_ptr().write_bytes(7, 1);
+            ///     })
+            /// };
+            /// assert!(cell.borrow().iter().all(|&b| b == 7));
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub unsafe fn new_with(f: impl FnOnce(&mut MaybeUninit<T>)) -> Self {
+                // SAFETY: guaranteed by caller
+                let res = unsafe {
+                    Self::try_new_with(|data| {
+                        f(data);
+                        Ok::<_, std::convert::Infallible>(())
+                    })
+                };
+                match res {
+                    Ok(this) => this,
+                }
+            }
+
+            /// Like [`ThinCell::new_with`], but `f` may fail, in which case the
+            /// allocation is freed and the error is returned.
+            ///
+            /// # Safety
+            ///
+            /// `f` must fully initialize the value if it returns `Ok`. If it returns
+            /// `Err` or panics, the value is not dropped.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub unsafe fn try_new_with<E>(
+                f: impl FnOnce(&mut MaybeUninit<T>) -> Result<(), E>,
+            ) -> Result<Self, E> {
+                let layout = Layout::new::<Inner<T>>();
+                // SAFETY: `layout` has non-zero size
+                let Some(ptr) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+
+                /// Frees the allocation if `f` fails or panics, when it holds no value.
+                struct Free {
+                    ptr: NonNull<u8>,
+                    layout: Layout,
+                }
+
+                impl Drop for Free {
+                    fn drop(&mut self) {
+                        // SAFETY: The allocation is no longer used and holds no value
+                        unsafe { dealloc(self.ptr.as_ptr(), self.layout) }
+                    }
+                }
+
+                let free = Free { ptr, layout };
+                let inner = ptr.cast::<Inner<T>>().as_ptr();
+
+                // SAFETY: `inner` is a fresh allocation of `Inner<T>`, and
+                // `UnsafeCell<T>` has the same layout as `T`.
+                unsafe {
+                    (&raw mut (*inner).metadata).write(0);
+                    (&raw mut (*inner).state).write(State::new());
+                    f(&mut *(&raw mut (*inner).data).cast::<MaybeUninit<T>>())?;
+                }
+                // The value is initialized, guaranteed by caller
+                std::mem::forget(free);
+
+                let this = ThinCell {
+                    ptr: ptr.cast(),
                     _marker: PhantomData,
+                };
+                this.register();
+                Ok(this)
+            }
+
+            /// Creates a `ThinCell` for each of the given values, allocating all of them
+            /// from a single memory block.
+            ///
+            /// The block is freed once every cell created from it has been dropped, so
+            /// this is best suited to cells with similar lifetimes, e.g., tables set up
+            /// at startup.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cells = ThinCell::new_many(0..100);
+            ///
+            /// assert_eq!(cells.len(), 100);
+            /// assert_eq!(*cells[42].borrow(), 42);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new_many(values: impl IntoIterator<Item = T>) -> Vec<Self> {
+                let values = values.into_iter().collect::<Vec<_>>();
+                if values.is_empty() {
+                    return Vec::new();
+                }
+
+                let SlotsLayout { layout, first, stride } =
+                    SlotsLayout::new::<Block, Inner<T>>(values.len());
+
+                // SAFETY: `layout` has non-zero size
+                let Some(block) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+                let block = block.cast::<Block>();
+
+                // SAFETY: `block` is a fresh allocation for `Block` followed by the slots
+                unsafe {
+                    block.write(Block {
+                        header: Release {
+                            release: Block::release,
+                        },
+                        state: State::with_count(values.len()),
+                        layout,
+                    })
+                };
+
+                let mut cells = Vec::with_capacity(values.len());
+                for (i, data) in values.into_iter().enumerate() {
+                    // SAFETY: slot `i` lies within the allocation as computed by
+                    // `SlotsLayout`, and is only written once.
+                    let cell = unsafe {
+                        let ptr = block.cast::<u8>().add(first + i * stride).cast::<()>();
+                        set_header(ptr, block.cast());
+                        ptr.cast::<Inner<T>>().write(Inner {
+                            metadata: 0,
+                            state: State::new_external(),
+                            data: UnsafeCell::new(data),
+                        });
+
+                        ThinCell {
+                            ptr,
+                            _marker: PhantomData,
+                        }
+                    };
+                    cell.register();
+                    cells.push(cell);
                 }
+                cells
+            }
+
+            /// Creates a `ThinCell` holding `a` and another one holding `b`, allocating
+            /// both from a single memory block.
+            ///
+            /// The two cells are independent, with their own owners and borrow flags,
+            /// and the block is freed once both have been dropped. This suits values
+            /// created and dropped together, e.g., the two ends of a channel.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let (name, hits) = ThinCell::new_pair(String::from("index"), 0u64);
+            ///
+            /// *hits.borrow() += 1;
+            /// assert_eq!(*name.borrow(), "index");
+            /// assert_eq!(*hits.borrow(), 1);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new_pair<U>(a: T, b: U) -> (Self, ThinCell<U>) {
+                let PairLayout { layout, first, second } =
+                    PairLayout::new::<Block, Inner<T>, Inner<U>>();
+
+                // SAFETY: `layout` has non-zero size
+                let Some(block) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+                let block = block.cast::<Block>();
+
+                // SAFETY: `block` is a fresh allocation for `Block` followed by the slots,
+                // each written once at the offsets computed by `PairLayout`.
+                let (a, b) = unsafe {
+                    block.write(Block {
+                        header: Release {
+                            release: Block::release,
+                        },
+                        state: State::with_count(2),
+                        layout,
+                    });
+
+                    let a_ptr = block.cast::<u8>().add(first).cast::<()>();
+                    set_header(a_ptr, block.cast());
+                    a_ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new_external(),
+                        data: UnsafeCell::new(a),
+                    });
+
+                    let b_ptr = block.cast::<u8>().add(second).cast::<()>();
+                    set_header(b_ptr, block.cast());
+                    b_ptr.cast::<Inner<U>>().write(Inner {
+                        metadata: 0,
+                        state: State::new_external(),
+                        data: UnsafeCell::new(b),
+                    });
+
+                    (
+                        ThinCell {
+                            ptr: a_ptr,
+                            _marker: PhantomData,
+                        },
+                        ThinCell {
+                            ptr: b_ptr,
+                            _marker: PhantomData,
+                        },
+                    )
+                };
+                a.register();
+                b.register();
+                (a, b)
             }
 
             /// Consumes the `ThinCell` and try to get inner value.
@@ -79,6 +534,26 @@ macro_rules! thin_cell {
                 Ok(unsafe { self.unwrap_unchecked() })
             }
 
+            /// Consumes the `ThinCell` and moves the value into a new `Box`.
+            ///
+            /// Returns `Err(self)` if there are other owners or it is currently
+            /// borrowed, like [`try_unwrap`](ThinCell::try_unwrap).
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new([0u8; 16]);
+            /// let other = cell.clone();
+            ///
+            /// let cell = cell.into_box().unwrap_err();
+            /// drop(other);
+            /// let boxed: Box<[u8; 16]> = cell.into_box().unwrap();
+            /// ```
+            pub fn into_box(self) -> Result<Box<T>, Self> {
+                self.try_unwrap().map(Box::new)
+            }
+
             /// Consumes the `ThinCell`, returning the inner value.
             ///
             /// # Safety
@@ -86,14 +561,168 @@ macro_rules! thin_cell {
             /// currently borrowed.
             pub unsafe fn unwrap_unchecked(self) -> T {
                 let this = ManuallyDrop::new(self);
-                // SAFETY: guaranteed by caller to have unique ownership and is not borrowed
-                let inner = unsafe { Box::from_raw(this.inner_ptr() as *mut Inner<T>) };
+                this.unregister();
+
+                if this.state().load().is_external() {
+                    // SAFETY: guaranteed by caller to have unique ownership and is not
+                    // borrowed, and the value is moved out before releasing the memory.
+                    unsafe {
+                        let data = std::ptr::read(this.inner().data.get());
+                        release(this.ptr);
+                        return data;
+                    }
+                }
+
+                // SAFETY: guaranteed by caller to have unique ownership and is not
+                // borrowed, and the value is moved out before freeing the memory.
+                unsafe {
+                    let data = std::ptr::read(this.inner().data.get());
+                    dealloc(this.ptr.as_ptr().cast(), Layout::new::<Inner<T>>());
+                    data
+                }
+            }
+
+            /// Replaces the value with `value` in the same allocation, as if the cell
+            /// was dropped and created again, e.g. to reuse pooled objects.
+            ///
+            /// Returns `Err(self)` if there are other owners or it is currently
+            /// borrowed, like [`try_unwrap`](ThinCell::try_unwrap). The old value is
+            /// dropped once the cell holds the new one.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(vec![1, 2]);
+            /// let ptr = cell.as_ptr();
+            ///
+            /// let cell = cell.recycle(Vec::new()).unwrap();
+            /// assert_eq!(cell.as_ptr(), ptr);
+            /// assert!(cell.borrow().is_empty());
+            ///
+            /// let other = cell.clone();
+            /// assert!(cell.recycle(vec![3]).is_err());
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn recycle(self, value: T) -> Result<Self, Self> {
+                if !self.state().try_unwrap() {
+                    return Err(self);
+                }
+
+                self.unregister();
+                // SAFETY: As tested above, there are no other owners and it is not
+                // borrowed
+                let old = unsafe { std::ptr::replace(self.inner().data.get(), value) };
+                self.state().reset_unique();
+                self.register();
+
+                drop(old);
+                Ok(self)
+            }
+
+            /// Consumes the `ThinCell` and transforms its value with `f`.
+            ///
+            /// If `Inner<U>` has the same layout as `Inner<T>`, the new value is
+            /// written in place of the old one, reusing the allocation. Otherwise a
+            /// new allocation is made for it.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the `ThinCell` is currently shared (count > 1) or borrowed.
+            /// If `f` panics, the allocation is freed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// struct Connecting(u32);
+            /// struct Connected(u32);
+            ///
+            /// let cell = ThinCell::new(Connecting(7));
+            /// let ptr = cell.as_ptr();
+            ///
+            /// let cell = cell.map_value(|Connecting(id)| Connected(id));
+            /// assert_eq!(cell.as_ptr(), ptr);
+            /// assert_eq!(cell.borrow().0, 7);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn map_value<U>(self, f: impl FnOnce(T) -> U) -> ThinCell<U> {
+                let s = self.state().load();
+                assert!(!s.is_shared(), "Cannot map shared `ThinCell`");
+                assert!(!s.is_borrowed(), "Cannot map borrowed `ThinCell`");
+
+                let layout = Layout::new::<Inner<T>>();
+                if layout != Layout::new::<Inner<U>>() {
+                    // SAFETY: As tested above, the `ThinCell` is not shared nor borrowed
+                    return ThinCell::new(f(unsafe { self.unwrap_unchecked() }));
+                }
+
+                /// Frees the allocation if `f` panics, when it holds no value.
+                struct Free {
+                    ptr: NonNull<()>,
+                    layout: Layout,
+                    external: bool,
+                }
+
+                impl Drop for Free {
+                    fn drop(&mut self) {
+                        // SAFETY: The allocation is no longer used and holds no value
+                        unsafe {
+                            if self.external {
+                                release(self.ptr)
+                            } else {
+                                dealloc(self.ptr.as_ptr().cast(), self.layout)
+                            }
+                        }
+                    }
+                }
+
+                let this = ManuallyDrop::new(self);
+                this.unregister();
 
-                inner.data.into_inner()
+                let free = Free {
+                    ptr: this.ptr,
+                    layout,
+                    external: s.is_external(),
+                };
+                // SAFETY: As tested above, the `ThinCell` is not shared nor borrowed, and
+                // the value is not used after being moved out.
+                let value = f(unsafe { std::ptr::read(this.inner().data.get()) });
+                std::mem::forget(free);
+
+                // SAFETY: `Inner<U>` has the same layout as `Inner<T>`, and the state is
+                // kept as is with a single owner.
+                let cell = unsafe {
+                    let inner = this.ptr.cast::<Inner<U>>().as_ptr();
+                    UnsafeCell::raw_get(&raw const (*inner).data).write(value);
+                    ThinCell {
+                        ptr: this.ptr,
+                        _marker: PhantomData,
+                    }
+                };
+                cell.register();
+                cell
             }
         }
 
         impl<T: ?Sized> ThinCell<T> {
+            /// Maximum number of owners of a cell, derived from the layout of its
+            /// state word.
+            ///
+            /// Cloning a handle past it aborts the process, or leaks the cell with
+            /// the `saturating` feature. It is `2^(N - 2) - 1` on `N`-bit targets,
+            /// e.g. `1_073_741_823` on 32-bit and `16_383` on 16-bit ones, which is
+            /// low enough to be reachable by long-running programs. The
+            /// `abort-on-overflow` feature halves it.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// assert!(ThinCell::<()>::MAX_STRONG_COUNT >= (1 << (usize::BITS - 3)) - 1);
+            /// ```
+            pub const MAX_STRONG_COUNT: usize = crate::raw::MAX_COUNT;
+
             const IS_SIZED: bool = is_sized::<T>();
 
             /// Reconstructs the raw pointer to the inner allocation.
@@ -103,7 +732,8 @@ macro_rules! thin_cell {
                 if Self::IS_SIZED {
                     // SIZED CASE: Cast pointer-to-pointer
                     // Doing this trick to workaround Rust not allowing `ptr as *const Inner<T>`
-                    // due to `T` being `?Sized` directly even when we know it's `Sized`
+                    // due to `T` being `?Sized` directly even when we know it's `Sized`. Unlike
+                    // a round trip through `usize`, this keeps the provenance of `self.ptr`.
                     let ptr_ref = &ptr as *const *mut () as *const *const Inner<T>;
 
                     // SAFETY: `self.ptr` is a valid pointer of `Inner<T>`
@@ -119,7 +749,12 @@ macro_rules! thin_cell {
                     // - https://github.com/thepowersgang/stack_dst-rs/issues/14
                     // - https://github.com/uazu/stakker/blob/5821c30409c19ca9167808b669c928c94bc5f177/src/queue/flat.rs#L14-L17
                     // But this should be sound as per Rust's fat pointer and metadata construction
-                    FatPtr { ptr, metadata }.into_ptr()
+                    let inner = FatPtr { ptr, metadata }.into_ptr();
+
+                    #[cfg(thin_cell_validate)]
+                    crate::fat_ptr::validate_round_trip(inner, FatPtr { ptr, metadata });
+
+                    inner
                 }
             }
 
@@ -135,20 +770,147 @@ macro_rules! thin_cell {
 
             /// Deallocates the inner allocation.
             ///
+            /// If dropping the value panics, the memory is still released while
+            /// unwinding, or the process aborts with the `abort-on-drop-panic`
+            /// feature.
+            ///
             /// # Safety
             ///
             /// `self` must be the last owner and it must not be used after this call.
             unsafe fn drop_in_place(&mut self) {
-                drop(unsafe { Box::from_raw(self.inner_ptr() as *mut Inner<T>) })
+                /// Releases the memory of the cell when dropped, after the value.
+                struct Free<'a, T: ?Sized> {
+                    this: &'a ThinCell<T>,
+                    layout: Layout,
+                    external: bool,
+                }
+
+                impl<T: ?Sized> Drop for Free<'_, T> {
+                    fn drop(&mut self) {
+                        #[cfg(debug_assertions)]
+                        self.this.poison();
+
+                        // SAFETY: The value has been dropped, or its drop unwound
+                        unsafe {
+                            if self.external {
+                                release(self.this.ptr);
+                            } else {
+                                dealloc(self.this.ptr.as_ptr().cast(), self.layout);
+                            }
+                        }
+                    }
+                }
+
+                /// Aborts when dropped, i.e. if dropping the value unwinds.
+                #[cfg(feature = "abort-on-drop-panic")]
+                struct AbortOnUnwind;
+
+                #[cfg(feature = "abort-on-drop-panic")]
+                impl Drop for AbortOnUnwind {
+                    fn drop(&mut self) {
+                        std::process::abort();
+                    }
+                }
+
+                self.unregister();
+
+                let free = Free {
+                    this: self,
+                    layout: Layout::for_value(self.inner()),
+                    external: self.state().load().is_external(),
+                };
+                #[cfg(feature = "abort-on-drop-panic")]
+                let abort = AbortOnUnwind;
+
+                // SAFETY: The value is dropped before its memory is released by
+                // `free`, which happens even if this panics.
+                unsafe { std::ptr::drop_in_place(self.inner_ptr() as *mut Inner<T>) };
+
+                #[cfg(feature = "abort-on-drop-panic")]
+                std::mem::forget(abort);
+                drop(free);
+            }
+
+            /// Overwrites the header with [`POISON`](crate::state::POISON) before the
+            /// memory is freed, so that later uses of leaked pointers abort in debug
+            /// builds instead of silently reading freed memory.
+            #[cfg(debug_assertions)]
+            fn poison(&self) {
+                self.state().poison();
+                // SAFETY: `Inner` is `repr(C)` and has `metadata` at offset 0, and the
+                // state is no longer used.
+                unsafe { *(self.ptr.as_ptr() as *mut usize) = crate::state::POISON };
+            }
+
+            /// Aborts if the cell has been freed, see [`poison`](ThinCell::poison).
+            #[cfg(debug_assertions)]
+            #[track_caller]
+            fn check_poison(&self) {
+                // Read at a fixed offset, as the metadata of a freed cell is poisoned
+                // as well.
+                // SAFETY: The cell is either live or a freed cell whose use is being
+                // diagnosed
+                let state = unsafe {
+                    self.ptr
+                        .byte_add(crate::raw::STATE_OFFSET)
+                        .cast::<State>()
+                        .as_ref()
+                };
+                state.check_poison();
+            }
+
+            /// Records the new allocation in the `debug` registry and `stats` counters,
+            /// and reports it to the `profile` profiler and `tracing`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            #[inline(always)]
+            fn register(&self) {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    addr = self.addr(),
+                    type_name = std::any::type_name::<T>(),
+                    "new"
+                );
+                #[cfg(feature = "stats")]
+                crate::stats::allocated(self.allocated_size());
+                #[cfg(feature = "debug")]
+                crate::debug::register(
+                    self.as_ptr(),
+                    std::any::type_name::<T>(),
+                    std::panic::Location::caller(),
+                );
+                #[cfg(feature = "profile")]
+                crate::profile::allocated(crate::profile::Allocation {
+                    addr: self.addr(),
+                    size: self.allocated_size(),
+                    type_name: std::any::type_name::<T>(),
+                    location: std::panic::Location::caller(),
+                });
+            }
+
+            /// Removes the allocation from the `debug` registry, `stats` counters and
+            /// borrow history, and reports it to the `profile` profiler and `tracing`.
+            #[inline(always)]
+            fn unregister(&self) {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = self.addr(), "free");
+                #[cfg(feature = "stats")]
+                crate::stats::freed(self.allocated_size());
+                #[cfg(feature = "borrow-history")]
+                self.state().forget();
+                #[cfg(feature = "debug")]
+                crate::debug::unregister(self.as_ptr());
+                #[cfg(feature = "profile")]
+                crate::profile::freed(self.addr(), self.allocated_size());
             }
 
             /// Leaks the `ThinCell`, returning a raw pointer to the inner allocation.
             ///
             /// The returned pointer points to the inner allocation. To restore the
             /// `ThinCell`, use [`ThinCell::from_raw`].
-            pub fn leak(self) -> *mut () {
-                let this = ManuallyDrop::new(self);
-                this.ptr.as_ptr()
+            pub const fn leak(self) -> *mut () {
+                let ptr = self.ptr;
+                std::mem::forget(self);
+                ptr.as_ptr()
             }
 
             /// Reconstructs a `ThinCell<T>` from a raw pointer.
@@ -157,12 +919,99 @@ macro_rules! thin_cell {
             /// The pointer must have been obtained from a previous call to
             /// [`ThinCell::leak`], and the [`ThinCell`] must not have been dropped in
             /// the meantime.
+            #[cfg_attr(debug_assertions, track_caller)]
             pub unsafe fn from_raw(ptr: *mut ()) -> Self {
-                ThinCell {
+                let this = ThinCell {
                     // SAFETY: caller guarantees `ptr` is valid
                     ptr: unsafe { NonNull::new_unchecked(ptr) },
                     _marker: PhantomData,
+                };
+                #[cfg(debug_assertions)]
+                this.check_poison();
+                this
+            }
+
+            /// Leaks the `ThinCell` like [`leak`](ThinCell::leak), storing `tag` in
+            /// the low bits of the returned pointer.
+            ///
+            /// The allocation is aligned to at least a `usize`, which leaves
+            /// [`TAG_MASK`](crate::raw::TAG_MASK) bits (2 or 3) free, e.g. to tell apart
+            /// the kinds of operations sharing an io_uring `user_data` word. Restore
+            /// the cell and the tag with [`ThinCell::from_raw_tagged`].
+            ///
+            /// # Panics
+            ///
+            /// Panics if `tag` doesn't fit in [`TAG_MASK`](crate::raw::TAG_MASK).
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let ptr = ThinCell::new(5).leak_tagged(3);
+            ///
+            /// let (cell, tag) = unsafe { ThinCell::<i32>::from_raw_tagged(ptr) };
+            /// assert_eq!(tag, 3);
+            /// assert_eq!(*cell.borrow(), 5);
+            /// ```
+            pub fn leak_tagged(self, tag: u8) -> *mut () {
+                assert!(
+                    usize::from(tag) <= crate::raw::TAG_MASK,
+                    "tag {tag} doesn't fit in the alignment bits"
+                );
+                self.leak().map_addr(|addr| addr | usize::from(tag))
+            }
+
+            /// Reconstructs a `ThinCell<T>` and its tag from a pointer returned by
+            /// [`ThinCell::leak_tagged`].
+            ///
+            /// # Safety
+            ///
+            /// The pointer must have been obtained from a previous call to
+            /// [`ThinCell::leak_tagged`], and the [`ThinCell`] must not have been
+            /// dropped in the meantime.
+            #[cfg_attr(debug_assertions, track_caller)]
+            pub unsafe fn from_raw_tagged(ptr: *mut ()) -> (Self, u8) {
+                let tag = ptr.addr() & crate::raw::TAG_MASK;
+                let ptr = ptr.map_addr(|addr| addr & !crate::raw::TAG_MASK);
+                // SAFETY: guaranteed by caller, with the tag cleared
+                (unsafe { ThinCell::from_raw(ptr) }, tag as u8)
+            }
+
+            /// Drops the handle, deferring the drop of the value to
+            /// [`drain_deferred`](crate::drain_deferred) if it is the last owner.
+            ///
+            /// This keeps the destruction of large values (big buffers, deep
+            /// structures) out of latency-sensitive code, e.g. to spread it over
+            /// event loop turns. Values are queued on the current thread, which
+            /// drops the remaining ones when it exits.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(vec![0u8; 1 << 20]);
+            /// cell.clone().drop_deferred();
+            /// assert_eq!(thin_cell::deferred_len(), 0);
+            ///
+            /// cell.drop_deferred();
+            /// assert_eq!(thin_cell::drain_deferred(8), 1);
+            /// ```
+            pub fn drop_deferred(self)
+            where
+                T: 'static,
+            {
+                /// Restores and drops the handle leaked by `drop_deferred`.
+                unsafe fn drop_leaked<T: ?Sized>(ptr: *mut ()) {
+                    // SAFETY: `ptr` was leaked from a `ThinCell<T>`
+                    drop(unsafe { ThinCell::<T>::from_raw(ptr) });
+                }
+
+                if self.state().load_acquire().is_shared() {
+                    return;
                 }
+
+                // SAFETY: `drop_leaked::<T>` drops the `ThinCell<T>` leaked here
+                unsafe { crate::deferred::push(self.leak(), drop_leaked::<T>) };
             }
 
             /// Returns the number of owners.
@@ -170,6 +1019,23 @@ macro_rules! thin_cell {
                 self.state().load().count()
             }
 
+            /// Returns the size in bytes of the heap allocation backing this
+            /// `ThinCell`.
+            ///
+            /// This includes the header, any padding and the value itself (with the
+            /// full length for slices and `str`), and does not borrow the value.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new([0u8; 16]).unsize_slice();
+            /// assert_eq!(cell.allocated_size(), 2 * size_of::<usize>() + 16);
+            /// ```
+            pub fn allocated_size(&self) -> usize {
+                std::mem::size_of_val(self.inner())
+            }
+
             /// Borrows the value mutably.
             ///
             /// Returns a [`Ref`] guard that provides mutable access to the inner value.
@@ -189,18 +1055,16 @@ macro_rules! thin_cell {
             ///
             /// assert_eq!(*cell.borrow(), 10);
             /// ```
+            #[track_caller]
             pub fn borrow(&self) -> Ref<'_, T> {
+                #[cfg(debug_assertions)]
+                self.check_poison();
+
                 let inner = self.inner();
                 inner.state.borrow();
 
-                // SAFETY: We have exclusive access via borrow flag and block further access
-                // with `Ordering::Acquire`/`Release` pair.
-                let value = unsafe { &mut *inner.data.get() };
-
-                Ref {
-                    value,
-                    state: &inner.state,
-                }
+                // SAFETY: We have exclusive access via borrow flag
+                unsafe { Ref::new(inner) }
             }
 
             /// Attempts to borrow the value mutably.
@@ -221,35 +1085,239 @@ macro_rules! thin_cell {
             /// drop(borrow1);
             /// assert!(cell.try_borrow().is_some()); // Now available
             /// ```
+            #[cfg_attr(feature = "track-borrow", track_caller)]
             pub fn try_borrow(&self) -> Option<Ref<'_, T>> {
                 let inner = self.inner();
                 if !inner.state.try_borrow() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(addr = self.addr(), "borrow conflict");
                     return None;
                 }
 
-                // SAFETY: We have exclusive access via borrow flag and block further access
-                // with `Ordering::Acquire`/`Release` pair.
-                let value = unsafe { &mut *inner.data.get() };
-
-                Some(Ref {
-                    value,
-                    state: &inner.state,
-                })
+                // SAFETY: We have exclusive access via borrow flag
+                Some(unsafe { Ref::new(inner) })
             }
 
-            /// Get a mutable reference to the inner value without any checks.
+            /// Same as [`borrow`](ThinCell::borrow), named after
+            /// `RefCell::borrow_mut` to ease porting code from `Rc<RefCell<T>>`.
             ///
-            /// # Safety
+            /// All borrows of a `ThinCell` are mutable, so `borrow` never gives
+            /// shared access despite its name. The next major version is planned to
+            /// deprecate `borrow` in favour of this method.
             ///
-            /// The caller must guarantee that there are no other owners and it is not
-            /// borrowed now and during the entire lifetime of the returned reference.
-            pub unsafe fn borrow_unchecked(&mut self) -> &mut T {
-                let inner = self.inner();
-                unsafe { &mut *inner.data.get() }
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(vec![1]);
+            /// cell.borrow_mut().push(2);
+            /// assert_eq!(*cell.borrow_mut(), [1, 2]);
+            /// ```
+            #[track_caller]
+            pub fn borrow_mut(&self) -> Ref<'_, T> {
+                self.borrow()
             }
 
-            /// Creates a new `ThinCell<U>` from `data: U` and coerces it to
-            /// `ThinCell<T>`.
+            /// Same as [`try_borrow`](ThinCell::try_borrow), named after
+            /// `RefCell::try_borrow_mut`, see [`borrow_mut`](ThinCell::borrow_mut).
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow_mut(&self) -> Option<Ref<'_, T>> {
+                self.try_borrow()
+            }
+
+            /// Borrows this cell and `other` mutably at the same time.
+            ///
+            /// The cells are borrowed in address order, so that two threads borrowing
+            /// the same pair of `sync` cells never deadlock.
+            ///
+            /// # Panics
+            ///
+            /// Panics if both are handles of the same cell, or if either is already
+            /// borrowed (or blocks for the `sync` flavour).
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let from = ThinCell::new(vec![1, 2]);
+            /// let to = ThinCell::new(vec![]);
+            ///
+            /// let (mut from, mut to) = from.borrow_two(&to);
+            /// to.append(&mut from);
+            /// assert_eq!(*to, [1, 2]);
+            /// ```
+            #[track_caller]
+            pub fn borrow_two<'a, U: ?Sized>(
+                &'a self,
+                other: &'a ThinCell<U>,
+            ) -> (Ref<'a, T>, Ref<'a, U>) {
+                assert!(
+                    !self.ptr_eq_with(other),
+                    "Cannot borrow the same `ThinCell` twice"
+                );
+
+                if self.as_ptr() < other.as_ptr() {
+                    let this = self.borrow();
+                    (this, other.borrow())
+                } else {
+                    let other = other.borrow();
+                    (self.borrow(), other)
+                }
+            }
+
+            /// Borrows `N` distinct cells mutably at the same time.
+            ///
+            /// The cells are borrowed in address order like
+            /// [`borrow_two`](ThinCell::borrow_two), and the guards are returned in the
+            /// order of `cells`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if any two of `cells` are handles of the same cell, or if any
+            /// of them is already borrowed (or blocks for the `sync` flavour).
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cells = [ThinCell::new(1), ThinCell::new(2), ThinCell::new(3)];
+            ///
+            /// let [mut a, b, c] = ThinCell::borrow_many([&cells[0], &cells[1], &cells[2]]);
+            /// *a += *b + *c;
+            /// drop((a, b, c));
+            ///
+            /// assert_eq!(*cells[0].borrow(), 6);
+            /// ```
+            #[track_caller]
+            pub fn borrow_many<const N: usize>(cells: [&ThinCell<T>; N]) -> [Ref<'_, T>; N] {
+                let mut order: [usize; N] = std::array::from_fn(|i| i);
+                order.sort_unstable_by_key(|&i| cells[i].as_ptr());
+                assert!(
+                    order
+                        .windows(2)
+                        .all(|w| !cells[w[0]].ptr_eq(cells[w[1]])),
+                    "Cannot borrow the same `ThinCell` twice"
+                );
+
+                let mut guards: [Option<Ref<'_, T>>; N] = std::array::from_fn(|_| None);
+                for i in order {
+                    guards[i] = Some(cells[i].borrow());
+                }
+                guards.map(Option::unwrap)
+            }
+
+            /// Returns a shared reference to the value without a guard, or `None` if
+            /// it is currently borrowed.
+            ///
+            /// Unlike [`try_borrow`](ThinCell::try_borrow), this doesn't set the
+            /// borrow flag, so the cell can still be borrowed mutably while the
+            /// returned reference is alive. This mirrors
+            /// `RefCell::try_borrow_unguarded`.
+            ///
+            /// # Safety
+            ///
+            /// The caller must guarantee that the value is not borrowed (and thus not
+            /// mutated) during the entire lifetime of the returned reference.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(5);
+            ///
+            /// let guard = cell.borrow();
+            /// assert!(unsafe { cell.try_borrow_unguarded() }.is_none());
+            /// drop(guard);
+            ///
+            /// assert_eq!(unsafe { cell.try_borrow_unguarded() }, Some(&5));
+            /// ```
+            pub unsafe fn try_borrow_unguarded(&self) -> Option<&T> {
+                let inner = self.inner();
+                if inner.state.load_acquire().is_borrowed() {
+                    return None;
+                }
+
+                // SAFETY: Not borrowed now, and guaranteed by caller not to be borrowed
+                // while the reference is alive.
+                Some(unsafe { &*inner.data.get() })
+            }
+
+            /// Borrows the value mutably, checking the borrow flag in debug builds
+            /// only.
+            ///
+            /// With `debug_assertions`, this is the same as
+            /// [`borrow`](ThinCell::borrow). Otherwise the flag is neither checked nor
+            /// set, nor cleared when the guard is dropped, which saves a branch (or a
+            /// compare-and-swap for the `sync` flavour) in hot paths whose access
+            /// patterns have been validated by tests.
+            ///
+            /// # Safety
+            ///
+            /// The caller must guarantee that the value is not borrowed now and
+            /// during the entire lifetime of the returned guard.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(5);
+            ///
+            /// *unsafe { cell.borrow_debug_checked() } += 1;
+            /// assert_eq!(*cell.borrow(), 6);
+            /// ```
+            #[track_caller]
+            pub unsafe fn borrow_debug_checked(&self) -> DebugCheckedRef<'_, T> {
+                DebugCheckedRef {
+                    #[cfg(debug_assertions)]
+                    guard: self.borrow(),
+                    // SAFETY: Guaranteed by caller
+                    #[cfg(not(debug_assertions))]
+                    value: unsafe { self.borrow_unchecked() },
+                }
+            }
+
+            /// Get a mutable reference to the inner value without any checks.
+            ///
+            /// # Safety
+            ///
+            /// The caller must guarantee that the value is not borrowed or accessed
+            /// through any other handle now and during the entire lifetime of the
+            /// returned reference.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(5);
+            /// let other = cell.clone();
+            ///
+            /// *unsafe { other.borrow_unchecked() } += 1;
+            /// assert_eq!(*cell.borrow(), 6);
+            /// ```
+            #[allow(clippy::mut_from_ref)]
+            pub unsafe fn borrow_unchecked(&self) -> &mut T {
+                let inner = self.inner();
+                // Synchronize with the release of the last borrow
+                inner.state.acquire();
+
+                // SAFETY: Guaranteed by caller to have exclusive access
+                unsafe { &mut *inner.data.get() }
+            }
+
+            /// Same as [`borrow_unchecked`](ThinCell::borrow_unchecked), named after
+            /// `UnsafeCell::as_mut_unchecked`.
+            ///
+            /// # Safety
+            ///
+            /// See [`borrow_unchecked`](ThinCell::borrow_unchecked).
+            #[allow(clippy::mut_from_ref)]
+            pub unsafe fn as_mut_unchecked(&self) -> &mut T {
+                // SAFETY: Guaranteed by caller
+                unsafe { self.borrow_unchecked() }
+            }
+
+            /// Creates a new `ThinCell<U>` from `data: U` and coerces it to
+            /// `ThinCell<T>`.
             ///
             /// # Safety
             ///
@@ -258,6 +1326,7 @@ macro_rules! thin_cell {
             /// - a valid unsizing of `Inner<T>`, e.g., some `Inner<dyn Trait>` or
             ///   `Inner<[_]>`
             /// - with same address (bare data pointer without metadata) as input
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
             pub unsafe fn new_unsize<U>(
                 data: U,
                 coerce: impl Fn(*const Inner<U>) -> *const Inner<T>,
@@ -275,9 +1344,27 @@ macro_rules! thin_cell {
             ///
             /// # Panics
             ///
-            /// Panics if the `ThinCell` is currently shared (count > 1) or borrowed.
+            /// Panics if the `ThinCell` is currently shared (count > 1) or borrowed,
+            /// unless its header already holds the metadata of `U`, e.g. when a
+            /// handle downcast from `ThinCell<U>` is erased again. As the same vtable
+            /// may be duplicated across codegen units, this is best effort for trait
+            /// objects.
             ///
             /// See [`ThinCell::unsize_unchecked`] for details.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// use std::fmt::Debug;
+            ///
+            /// let cell = unsafe { ThinCell::<dyn Debug>::new_unsize(1, |p| p as _) };
+            /// let concrete = unsafe { cell.clone().downcast_unchecked::<i32>() };
+            ///
+            /// // Shared, but the header already holds the vtable of `i32: Debug`
+            /// let erased = unsafe { concrete.unsize::<dyn Debug>(|p| p as _) };
+            /// assert!(erased.ptr_eq(&cell));
+            /// ```
             pub unsafe fn unsize<U: ?Sized>(
                 self,
                 coerce: impl Fn(*const Inner<T>) -> *const Inner<U>,
@@ -285,6 +1372,21 @@ macro_rules! thin_cell {
                 let inner = self.inner();
                 let s = inner.state.load();
 
+                if s.is_shared() || s.is_borrowed() {
+                    let FatPtr { metadata, .. } =
+                        FatPtr::from_ptr::<Inner<U>>(coerce(self.inner_ptr()));
+
+                    // Other handles only rely on the header not changing, so there's
+                    // nothing to check if it already holds the new metadata.
+                    if metadata == inner.metadata {
+                        let this = ManuallyDrop::new(self);
+                        return ThinCell {
+                            ptr: this.ptr,
+                            _marker: PhantomData,
+                        };
+                    }
+                }
+
                 assert!(!s.is_shared(), "Cannot coerce shared `ThinCell`");
                 assert!(!s.is_borrowed(), "Cannot coerce borrowed `ThinCell`");
 
@@ -327,15 +1429,54 @@ macro_rules! thin_cell {
             }
 
             /// Returns the raw pointer to the inner allocation.
-            pub fn as_ptr(&self) -> *const () {
+            pub const fn as_ptr(&self) -> *const () {
                 self.ptr.as_ptr()
             }
 
+            /// Returns the address of the inner allocation, e.g. to log or hash the
+            /// identity of the cell.
+            ///
+            /// Unlike `as_ptr() as usize`, this doesn't expose the provenance of the
+            /// pointer, so the address can't be turned back into a `ThinCell`.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(1);
+            /// assert_eq!(cell.addr(), cell.clone().addr());
+            /// assert_ne!(cell.addr(), ThinCell::new(1).addr());
+            /// ```
+            pub fn addr(&self) -> usize {
+                self.ptr.addr().get()
+            }
+
             /// Returns `true` if the two `ThinCell`s point to the same allocation.
             pub fn ptr_eq(&self, other: &Self) -> bool {
                 std::ptr::eq(self.as_ptr(), other.as_ptr())
             }
 
+            /// Returns `true` if the two `ThinCell`s point to the same allocation,
+            /// even if they have different types, e.g. a concrete handle and one
+            /// unsized to `dyn Trait` from it.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// use std::any::Any;
+            ///
+            /// let dyn_cell = unsafe { ThinCell::<dyn Any>::new_unsize(1, |p| p as _) };
+            /// let cell = dyn_cell.clone().downcast::<i32>().unwrap();
+            /// assert!(cell.ptr_eq_with(&dyn_cell));
+            ///
+            /// let other = unsafe { ThinCell::<dyn Any>::new_unsize(1, |p| p as _) };
+            /// assert!(!cell.ptr_eq_with(&other));
+            /// ```
+            pub fn ptr_eq_with<U: ?Sized>(&self, other: &ThinCell<U>) -> bool {
+                std::ptr::eq(self.as_ptr(), other.as_ptr())
+            }
+
             /// Downcasts the `ThinCell<T>` to `ThinCell<U>`.
             ///
             /// # Safety
@@ -359,143 +1500,1680 @@ macro_rules! thin_cell {
             }
         }
 
-        /// Error returned by [`ThinCell::downcast`] when downcasting fails.
-        #[derive(Debug)]
-        pub enum DowncastError<T: ?Sized> {
-            /// The [`ThinCell`] is currently borrowed.
-            Borrowed(ThinCell<T>),
-
-            /// The inner value is not of the target type.
-            Type(ThinCell<T>),
-        }
-
-        impl<T: ?Sized> DowncastError<T> {
-            /// Consumes the error and returns the original `ThinCell<T>`.
-            pub fn into_inner(self) -> ThinCell<T> {
-                match self {
-                    DowncastError::Borrowed(cell) | DowncastError::Type(cell) => cell,
-                }
+        impl<T: Clone> ThinCell<[T]> {
+            /// Creates a cell holding the elements of all `parts` one after the
+            /// other, allocated once at the exact size.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let header = [0xff, 2];
+            /// let cell = ThinCell::<[u8]>::concat(&[&header, b"hi"]);
+            /// assert_eq!(&*cell.borrow(), [0xff, 2, b'h', b'i']);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn concat(parts: &[&[T]]) -> Self {
+                let len = parts
+                    .iter()
+                    .try_fold(0usize, |len, part| len.checked_add(part.len()))
+                    .expect("Slice too large for `ThinCell`");
+                Self::clone_from_iter(len, parts.iter().flat_map(|part| part.iter()))
             }
-        }
 
-        impl<T: Any + ?Sized> ThinCell<T> {
-            /// Attempts to downcast the `ThinCell<T>` to `ThinCell<U>`.
+            /// Clones the `len` elements yielded by `items` into a new cell,
+            /// allocated at the exact size.
             ///
-            /// # Returns
+            /// # Panics
             ///
-            /// - `Ok(ThinCell<U>)` if the inner value is of type `U` and is not
-            ///   currently borrowed
-            /// - `Err(DowncastError::Borrowed(self))` if the inner value is currently
-            ///   borrowed
-            /// - `Err(DowncastError::Type(self))` if the inner value is not of type `U`
-            pub fn downcast<U: Any>(self) -> Result<ThinCell<U>, DowncastError<T>> {
-                let inner = self.inner();
-                if !inner.state.try_borrow() {
-                    return Err(DowncastError::Borrowed(self));
+            /// Panics if `items` yields less than `len` elements.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn clone_from_iter<'a>(len: usize, items: impl Iterator<Item = &'a T>) -> Self
+            where
+                T: 'a,
+            {
+                // `Inner<[T]>` is `repr(C)`, so its fields are laid out like those
+                // of `Inner<[T; 0]>` followed by the elements.
+                let offset = std::mem::offset_of!(Inner<[T; 0]>, data);
+                let size = size_of::<T>()
+                    .checked_mul(len)
+                    .and_then(|size| size.checked_add(offset))
+                    .expect("Slice too large for `ThinCell`");
+                let layout = Layout::from_size_align(size, align_of::<Inner<[T; 0]>>())
+                    .expect("Slice too large for `ThinCell`")
+                    .pad_to_align();
+
+                // SAFETY: `layout` has non-zero size
+                let Some(ptr) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+
+                /// Drops the elements cloned so far and frees the allocation if
+                /// cloning panics.
+                struct Partial<T> {
+                    ptr: NonNull<u8>,
+                    data: NonNull<T>,
+                    len: usize,
+                    layout: Layout,
                 }
 
-                // SAFETY: We have exclusive access via borrow flag.
-                let data_ref = unsafe { &*inner.data.get() };
-                let type_id = data_ref.type_id();
-                inner.state.unborrow();
-
-                if TypeId::of::<U>() == type_id {
-                    // SAFETY: We have verified that the inner value is of type `U`
-                    Ok(unsafe { self.downcast_unchecked::<U>() })
-                } else {
-                    Err(DowncastError::Type(self))
+                impl<T> Drop for Partial<T> {
+                    fn drop(&mut self) {
+                        // SAFETY: The first `len` elements are initialized, and the
+                        // allocation is not used by anything else.
+                        unsafe {
+                            NonNull::slice_from_raw_parts(self.data, self.len).drop_in_place();
+                            dealloc(self.ptr.as_ptr(), self.layout);
+                        }
+                    }
                 }
+
+                // SAFETY: `ptr` is a fresh allocation of `layout`, whose header is
+                // that of `Inner<[T; 0]>`, followed by room for the elements.
+                let this = unsafe {
+                    ptr.cast::<Inner<[T; 0]>>().write(Inner {
+                        metadata: len,
+                        state: State::new(),
+                        data: UnsafeCell::new([]),
+                    });
+
+                    let mut partial = Partial {
+                        ptr,
+                        data: ptr.add(offset).cast::<T>(),
+                        len: 0,
+                        layout,
+                    };
+                    for item in items.take(len) {
+                        partial.data.add(partial.len).write(item.clone());
+                        partial.len += 1;
+                    }
+                    assert_eq!(partial.len, len, "Iterator yielded too few elements");
+                    std::mem::forget(partial);
+
+                    ThinCell::<[T]> {
+                        ptr: ptr.cast(),
+                        _marker: PhantomData,
+                    }
+                };
+                debug_assert_eq!(Layout::for_value(this.inner()), layout);
+                this.register();
+                this
             }
         }
 
-        /// `ThinCell` is `Unpin` as it does not move its inner data.
-        impl<T: ?Sized> Unpin for ThinCell<T> {}
-
-        impl<'a, T: ?Sized> Drop for Ref<'a, T> {
-            fn drop(&mut self) {
-                self.state.unborrow();
+        impl<T: Clone> From<&[T]> for ThinCell<[T]> {
+            /// Clones the elements into a new cell, allocated at the exact size.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(slice: &[T]) -> Self {
+                ThinCell::clone_from_iter(slice.len(), slice.iter())
             }
         }
 
-        impl<'a, T: ?Sized> Deref for Ref<'a, T> {
-            type Target = T;
+        impl<T> ThinCell<T> {
+            /// Moves the value out of a unique `Rc` into a new cell, or returns the
+            /// `Rc` back if it is shared.
+            ///
+            /// Weak references to the `Rc` don't prevent the conversion.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn try_from_rc(rc: std::rc::Rc<T>) -> Result<Self, std::rc::Rc<T>> {
+                std::rc::Rc::try_unwrap(rc).map(ThinCell::new)
+            }
 
-            fn deref(&self) -> &T {
-                self.value
+            /// Moves the value out of a unique `Arc` into a new cell, or returns the
+            /// `Arc` back if it is shared.
+            ///
+            /// Weak references to the `Arc` don't prevent the conversion.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn try_from_arc(arc: std::sync::Arc<T>) -> Result<Self, std::sync::Arc<T>> {
+                std::sync::Arc::try_unwrap(arc).map(ThinCell::new)
             }
         }
 
-        impl<'a, T: ?Sized> DerefMut for Ref<'a, T> {
-            fn deref_mut(&mut self) -> &mut T {
-                self.value
+        impl<T: Clone> From<std::rc::Rc<T>> for ThinCell<T> {
+            /// Moves the value into a new cell if the `Rc` is unique, or clones it
+            /// otherwise.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// use std::rc::Rc;
+            ///
+            /// let rc = Rc::new(vec![1]);
+            /// let shared = ThinCell::from(rc.clone());
+            /// shared.borrow().push(2);
+            /// assert_eq!(*rc, [1]);
+            ///
+            /// let unique = ThinCell::from(rc);
+            /// assert_eq!(*unique.borrow(), [1]);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(rc: std::rc::Rc<T>) -> Self {
+                ThinCell::new(std::rc::Rc::unwrap_or_clone(rc))
             }
         }
 
-        impl<'a, T: Debug + ?Sized> Debug for Ref<'a, T> {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                Debug::fmt(&**self, f)
+        impl<T: Clone> From<std::sync::Arc<T>> for ThinCell<T> {
+            /// Moves the value into a new cell if the `Arc` is unique, or clones it
+            /// otherwise.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(arc: std::sync::Arc<T>) -> Self {
+                ThinCell::new(std::sync::Arc::unwrap_or_clone(arc))
             }
         }
 
-        impl<'a, T: Display + ?Sized> Display for Ref<'a, T> {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                Display::fmt(&**self, f)
+        #[cfg(feature = "triomphe")]
+        impl<T: Clone> From<triomphe::Arc<T>> for ThinCell<T> {
+            /// Moves the value into a new cell if the `Arc` is unique, or clones it
+            /// otherwise.
+            ///
+            /// Only available with the `triomphe` feature.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(arc: triomphe::Arc<T>) -> Self {
+                ThinCell::new(triomphe::Arc::try_unwrap(arc).unwrap_or_else(|arc| (*arc).clone()))
             }
         }
 
-        impl<T: ?Sized> Clone for ThinCell<T> {
-            fn clone(&self) -> Self {
-                self.state().inc();
+        #[cfg(feature = "triomphe")]
+        impl<T> TryFrom<ThinCell<T>> for triomphe::Arc<T> {
+            type Error = ThinCell<T>;
 
-                ThinCell {
-                    ptr: self.ptr,
-                    _marker: PhantomData,
-                }
+            /// Moves the value into a new `Arc`, or returns the cell back if there are
+            /// other owners or it is currently borrowed.
+            ///
+            /// Only available with the `triomphe` feature.
+            fn try_from(cell: ThinCell<T>) -> Result<Self, Self::Error> {
+                cell.try_unwrap().map(triomphe::Arc::new)
             }
         }
 
-        impl<T: ?Sized> Drop for ThinCell<T> {
-            fn drop(&mut self) {
-                let inner = self.inner();
-                if !inner.state.dec() {
-                    // Not last owner, nothing to do
-                    return;
-                }
-
-                // SAFETY: We are the last owner, so we have unique ownership, and we're not
-                // using `self` after this call.
-                unsafe {
-                    self.drop_in_place();
-                }
+        #[cfg(feature = "triomphe")]
+        impl<T: Clone> From<&triomphe::ThinArc<(), T>> for ThinCell<[T]> {
+            /// Clones the elements of `arc` into a new cell.
+            ///
+            /// Only available with the `triomphe` feature.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(arc: &triomphe::ThinArc<(), T>) -> Self {
+                ThinCell::from(&arc.slice[..])
             }
         }
 
-        impl<T: Default> Default for ThinCell<T> {
-            fn default() -> Self {
-                ThinCell::new(T::default())
+        #[cfg(feature = "triomphe")]
+        impl<T> TryFrom<ThinCell<[T]>> for triomphe::ThinArc<(), T> {
+            type Error = ThinCell<[T]>;
+
+            /// Moves the elements into a new `ThinArc`, or returns the cell back if
+            /// there are other owners or it is currently borrowed.
+            ///
+            /// Only available with the `triomphe` feature.
+            fn try_from(cell: ThinCell<[T]>) -> Result<Self, Self::Error> {
+                let elements = cell.into_vec()?;
+                Ok(triomphe::ThinArc::from_header_and_iter((), elements.into_iter()))
             }
         }
 
-        impl<T: Debug + ?Sized> Debug for ThinCell<T> {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                let inner = self.inner();
-                let state = inner.state.load();
-                let mut d = f.debug_struct("ThinCell");
-                match self.try_borrow() {
-                    Some(borrowed) => d.field("value", &borrowed),
-                    None => d.field("value", &"<borrowed>"),
-                }
-                .field("state", &state)
-                .finish()
+        impl<T: Clone> ThinCell<T> {
+            /// Creates a new, independent cell with a clone of the value.
+            ///
+            /// Unlike [`clone`](Clone::clone), which only adds an owner of the same
+            /// cell, changes to the new cell are not seen through this one.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(vec![1]);
+            /// let fork = cell.deep_clone();
+            ///
+            /// fork.borrow().push(2);
+            /// assert_eq!(*cell.borrow(), [1]);
+            /// assert!(!fork.ptr_eq(&cell));
+            /// ```
+            #[track_caller]
+            pub fn deep_clone(&self) -> ThinCell<T> {
+                ThinCell::new(self.borrow().clone())
             }
         }
 
-        impl<T: Display + ?Sized> Display for ThinCell<T> {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                match self.try_borrow() {
-                    Some(borrowed) => Display::fmt(&*borrowed, f),
-                    None => write!(f, "<borrowed>"),
+        impl<T> ThinCell<[T]> {
+            /// Consumes the `ThinCell` and moves the elements into a new `Vec`.
+            ///
+            /// Returns `Err(self)` if there are other owners or it is currently
+            /// borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new([String::from("a"), String::from("b")]).unsize_slice();
+            /// assert_eq!(cell.into_vec().unwrap(), ["a", "b"]);
+            /// ```
+            pub fn into_vec(self) -> Result<Vec<T>, Self> {
+                if !self.state().try_unwrap() {
+                    return Err(self);
+                }
+
+                // SAFETY: As tested above, there are no other owners and it is not borrowed
+                Ok(unsafe { self.into_vec_unchecked() })
+            }
+
+            /// Moves the elements into a new `Vec` and frees the allocation.
+            ///
+            /// # Safety
+            ///
+            /// The caller must guarantee that there are no other owners and it is not
+            /// currently borrowed.
+            unsafe fn into_vec_unchecked(self) -> Vec<T> {
+                let this = ManuallyDrop::new(self);
+                this.unregister();
+
+                let inner = this.inner();
+                let layout = Layout::for_value(inner);
+                let len = inner.metadata;
+
+                let mut vec = Vec::<T>::with_capacity(len);
+                // SAFETY: guaranteed by caller to have unique ownership and is not
+                // borrowed, and the elements are moved out before releasing the memory.
+                unsafe {
+                    vec.as_mut_ptr()
+                        .copy_from_nonoverlapping(inner.data.get().cast::<T>(), len);
+                    vec.set_len(len);
+
+                    if this.state().load().is_external() {
+                        release(this.ptr);
+                    } else {
+                        dealloc(this.ptr.as_ptr().cast(), layout);
+                    }
+                }
+                vec
+            }
+        }
+
+        impl ThinCell<[u8]> {
+            /// Creates a new zeroed buffer of `len` bytes, aligned to at least
+            /// `align` bytes, e.g. 512 or 4096 for direct I/O.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `align` is not a power of two or the size overflows.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let buf = ThinCell::<[u8]>::new_aligned_zeroed(8192, 4096);
+            /// assert_eq!(buf.borrow().len(), 8192);
+            /// assert_eq!(buf.borrow().as_ptr().addr() % 4096, 0);
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new_aligned_zeroed(len: usize, align: usize) -> Self {
+                let offset = std::mem::offset_of!(Inner<[u8; 0]>, data);
+                let size = offset.checked_add(len).expect("capacity overflow");
+                let ptr = Aligned::alloc(size, offset, align.max(align_of::<Inner<[u8; 0]>>()));
+
+                // SAFETY: `ptr` is valid for writes of the header followed by `len`
+                // bytes
+                unsafe {
+                    ptr.cast::<Inner<[u8; 0]>>().write(Inner {
+                        metadata: len,
+                        state: State::new_external(),
+                        data: UnsafeCell::new([]),
+                    });
+                    ptr.cast::<u8>().add(offset).write_bytes(0, len);
+                }
+
+                let this = ThinCell {
+                    ptr,
+                    _marker: PhantomData,
+                };
+                this.register();
+                this
+            }
+        }
+
+        impl<T: Clone> ThinCell<[T]> {
+            /// Creates a new, independent cell with a clone of the elements.
+            ///
+            /// See [`ThinCell::deep_clone`].
+            #[track_caller]
+            pub fn deep_clone(&self) -> ThinCell<[T]> {
+                ThinCell::from(&*self.borrow())
+            }
+        }
+
+        #[cfg(feature = "bytemuck")]
+        impl<T: bytemuck::Pod> ThinCell<[T]> {
+            /// Reinterprets the elements as `U`s in place, without copying.
+            ///
+            /// Returns `Err(self)` if there are other owners or the value is borrowed,
+            /// if the length in bytes is not a multiple of `size_of::<U>()`, or if `U`
+            /// needs the allocation to be aligned differently than `T`, i.e., their
+            /// alignments differ and one of them exceeds that of `usize`.
+            ///
+            /// Only available with the `bytemuck` feature.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::<[u8]>::from(&[1, 0, 0, 0, 2, 0, 0, 0][..]);
+            ///
+            /// let cell = cell.cast::<u32>().unwrap();
+            /// assert_eq!(cell.borrow().len(), 2);
+            /// assert!(cell.cast::<[u8; 3]>().is_err());
+            /// ```
+            pub fn cast<U: bytemuck::Pod>(self) -> Result<ThinCell<[U]>, Self> {
+                let s = self.state().load();
+                let align = |align: usize| align.max(align_of::<usize>());
+                let same_align = align(align_of::<T>()) == align(align_of::<U>());
+                if s.is_shared() || s.is_borrowed() || !same_align {
+                    return Err(self);
+                }
+
+                // SAFETY: There are no other owners and it is not borrowed
+                let data = unsafe { &*self.inner().data.get() };
+                let Ok(len) = bytemuck::try_cast_slice::<T, U>(data).map(<[U]>::len) else {
+                    return Err(self);
+                };
+
+                let this = ManuallyDrop::new(self);
+                // SAFETY: No other handle reads the header, and the value and the
+                // allocation keep the same size and alignment. `Inner` is `repr(C)` and
+                // has `metadata` at offset 0.
+                unsafe { *(this.ptr.as_ptr() as *mut usize) = len };
+
+                Ok(ThinCell {
+                    ptr: this.ptr,
+                    _marker: PhantomData,
+                })
+            }
+        }
+
+        impl ThinCell<str> {
+            /// Consumes the `ThinCell` and moves the string into a new `String`.
+            ///
+            /// Returns `Err(self)` if there are other owners or it is currently
+            /// borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::<str>::from("thin");
+            /// let other = cell.clone();
+            ///
+            /// let cell = cell.into_string().unwrap_err();
+            /// drop(other);
+            /// assert_eq!(cell.into_string().unwrap(), "thin");
+            /// ```
+            pub fn into_string(self) -> Result<String, Self> {
+                if !self.state().try_unwrap() {
+                    return Err(self);
+                }
+
+                // SAFETY: As tested above, there are no other owners and it is not
+                // borrowed, and `[u8]` has the same layout and metadata as `str`.
+                unsafe {
+                    let bytes = self.unsize_unchecked(|ptr| ptr as *const Inner<[u8]>);
+                    Ok(String::from_utf8_unchecked(bytes.into_vec_unchecked()))
+                }
+            }
+
+            /// Creates a new, independent cell with a copy of the string.
+            ///
+            /// See [`ThinCell::deep_clone`].
+            #[track_caller]
+            pub fn deep_clone(&self) -> ThinCell<str> {
+                ThinCell::from(&*self.borrow())
+            }
+        }
+
+        impl From<&str> for ThinCell<str> {
+            /// Copies the string into a new cell, allocated at the exact size.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let name = String::from("thin");
+            /// let cell = ThinCell::<str>::from(name.as_str());
+            /// assert_eq!(&*cell.borrow(), "thin");
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(s: &str) -> Self {
+                let cell = ThinCell::<[u8]>::from(s.as_bytes());
+
+                // SAFETY: The bytes are valid UTF-8 as they come from `s`, and `str` has the
+                // same layout and metadata as `[u8]`.
+                unsafe { cell.unsize(|ptr| ptr as *const Inner<str>) }
+            }
+        }
+
+        impl ThinCell<str> {
+            /// Creates a cell holding all `parts` one after the other, allocated
+            /// once at the exact size.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::<str>::concat(&["GET ", "/", " HTTP/1.1"]);
+            /// assert_eq!(&*cell.borrow(), "GET / HTTP/1.1");
+            /// ```
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn concat(parts: &[&str]) -> Self {
+                let len = parts
+                    .iter()
+                    .try_fold(0usize, |len, part| len.checked_add(part.len()))
+                    .expect("Slice too large for `ThinCell`");
+                let cell = ThinCell::<[u8]>::clone_from_iter(
+                    len,
+                    parts.iter().flat_map(|part| part.as_bytes()),
+                );
+
+                // SAFETY: The bytes are valid UTF-8 as they are a concatenation of
+                // `str`s, and `str` has the same layout and metadata as `[u8]`.
+                unsafe { cell.unsize(|ptr| ptr as *const Inner<str>) }
+            }
+
+            /// Copies `s` into a new cell of `N` bytes, used by `thin_str!`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `s` is not `N` bytes long.
+            #[doc(hidden)]
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn __from_str<const N: usize>(s: &str) -> Self {
+                let bytes: [u8; N] = s.as_bytes().try_into().unwrap();
+                let cell = ThinCell::new(bytes).unsize_slice();
+
+                // SAFETY: The bytes are valid UTF-8 as they come from `s`, and `str` has the
+                // same layout and metadata as `[u8]`.
+                unsafe { cell.unsize(|ptr| ptr as *const Inner<str>) }
+            }
+        }
+
+        impl<T> ThinCell<T> {
+            /// Replaces the value with `value`, returning the old one.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed. See [`try_replace`](ThinCell::try_replace) for a
+            /// non-panicking variant.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(5);
+            /// assert_eq!(cell.replace(6), 5);
+            /// assert_eq!(*cell.borrow(), 6);
+            /// ```
+            #[track_caller]
+            pub fn replace(&self, value: T) -> T {
+                std::mem::replace(&mut *self.borrow(), value)
+            }
+
+            /// Replaces the value with `value`, returning the old one, or returns
+            /// `value` back in the error if the cell is already borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let cell = ThinCell::new(5);
+            ///
+            /// let guard = cell.borrow();
+            /// let err = cell.try_replace(6).unwrap_err();
+            /// assert_eq!(err.into_inner(), 6);
+            /// drop(guard);
+            ///
+            /// assert_eq!(cell.try_replace(6).unwrap(), 5);
+            /// ```
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_replace(&self, value: T) -> Result<T, BorrowError<T>> {
+                match self.try_borrow() {
+                    Some(mut borrowed) => Ok(std::mem::replace(&mut *borrowed, value)),
+                    None => Err(BorrowError { value }),
+                }
+            }
+
+            /// Sets the value, dropping the old one after the cell is released.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed. See [`try_set`](ThinCell::try_set) for a non-panicking
+            /// variant.
+            #[track_caller]
+            pub fn set(&self, value: T) {
+                drop(self.replace(value));
+            }
+
+            /// Sets the value like [`set`](ThinCell::set), or returns `value` back in
+            /// the error if the cell is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_set(&self, value: T) -> Result<(), BorrowError<T>> {
+                self.try_replace(value).map(drop)
+            }
+
+            /// Swaps the values of two cells.
+            ///
+            /// Swapping a cell with itself (or another handle of it) does nothing.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if either cell is already
+            /// borrowed. See [`try_swap`](ThinCell::try_swap) for a non-panicking
+            /// variant.
+            #[track_caller]
+            pub fn swap(&self, other: &Self) {
+                if self.ptr_eq(other) {
+                    return;
+                }
+                let (mut this, mut other) = self.borrow_two(other);
+                std::mem::swap(&mut *this, &mut *other);
+            }
+
+            /// Swaps the values of two cells like [`swap`](ThinCell::swap), or
+            /// returns an error if either cell is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_swap(&self, other: &Self) -> Result<(), BorrowError> {
+                if self.ptr_eq(other) {
+                    return if self.state().load().is_borrowed() {
+                        Err(BorrowError { value: () })
+                    } else {
+                        Ok(())
+                    };
+                }
+                match (self.try_borrow(), other.try_borrow()) {
+                    (Some(mut this), Some(mut other)) => {
+                        std::mem::swap(&mut *this, &mut *other);
+                        Ok(())
+                    }
+                    _ => Err(BorrowError { value: () }),
+                }
+            }
+
+            /// Takes the value, leaving `Default::default()` in its place.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed. See [`try_take`](ThinCell::try_take) for a non-panicking
+            /// variant.
+            #[track_caller]
+            pub fn take(&self) -> T
+            where
+                T: Default,
+            {
+                self.replace(T::default())
+            }
+
+            /// Takes the value like [`take`](ThinCell::take), or returns an error if
+            /// the cell is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_take(&self) -> Result<T, BorrowError>
+            where
+                T: Default,
+            {
+                match self.try_borrow() {
+                    Some(mut borrowed) => Ok(std::mem::take(&mut *borrowed)),
+                    None => Err(BorrowError { value: () }),
+                }
+            }
+
+            /// Sets the value to `new` if it is equal to `expected`, in a single short
+            /// borrow.
+            ///
+            /// Returns the previous value in [`Ok`] if it was replaced, or the current
+            /// value in [`Err`] otherwise, like [`AtomicUsize::compare_exchange`].
+            ///
+            /// [`AtomicUsize::compare_exchange`]: std::sync::atomic::AtomicUsize::compare_exchange
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// #[derive(Clone, Copy, Debug, PartialEq)]
+            /// enum Phase {
+            ///     Idle,
+            ///     Running,
+            /// }
+            ///
+            /// let cell = ThinCell::new(Phase::Idle);
+            ///
+            /// assert_eq!(cell.compare_and_set(Phase::Idle, Phase::Running), Ok(Phase::Idle));
+            /// assert_eq!(cell.compare_and_set(Phase::Idle, Phase::Running), Err(Phase::Running));
+            /// ```
+            #[track_caller]
+            pub fn compare_and_set(&self, expected: T, new: T) -> Result<T, T>
+            where
+                T: Copy + PartialEq,
+            {
+                let mut borrowed = self.borrow();
+                let current = *borrowed;
+                if current == expected {
+                    *borrowed = new;
+                    Ok(current)
+                } else {
+                    Err(current)
+                }
+            }
+        }
+
+        impl<T> ThinCell<Option<T>> {
+            /// Borrows the value in the slot, inserting the result of `f` first if it
+            /// is empty.
+            ///
+            /// `f` is called while the cell is borrowed, so it must not borrow the
+            /// cell itself.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let slot = ThinCell::new(None);
+            ///
+            /// slot.get_or_insert_with(Vec::new).push(1);
+            /// slot.get_or_insert_with(|| unreachable!()).push(2);
+            /// assert_eq!(slot.take_inner(), Some(vec![1, 2]));
+            /// assert!(!slot.is_some());
+            /// ```
+            #[track_caller]
+            pub fn get_or_insert_with(&self, f: impl FnOnce() -> T) -> Ref<'_, T> {
+                Ref::map(self.borrow(), |slot| slot.get_or_insert_with(f))
+            }
+
+            /// Takes the value out of the slot, leaving it empty.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            #[track_caller]
+            pub fn take_inner(&self) -> Option<T> {
+                self.borrow().take()
+            }
+
+            /// Returns `true` if the slot holds a value.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            #[track_caller]
+            pub fn is_some(&self) -> bool {
+                self.borrow().is_some()
+            }
+
+            /// Returns `true` if the slot is empty.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed.
+            #[track_caller]
+            pub fn is_none(&self) -> bool {
+                self.borrow().is_none()
+            }
+        }
+
+        /// Error returned by the `try_` value operations of [`ThinCell`] when the
+        /// cell is already borrowed.
+        ///
+        /// Holds the value that could not be stored, if any.
+        pub struct BorrowError<T = ()> {
+            value: T,
+        }
+
+        impl<T> BorrowError<T> {
+            /// Consumes the error and returns the value that could not be stored.
+            pub fn into_inner(self) -> T {
+                self.value
+            }
+        }
+
+        impl<T> Debug for BorrowError<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("BorrowError").finish_non_exhaustive()
+            }
+        }
+
+        impl<T> Display for BorrowError<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("Already borrowed")
+            }
+        }
+
+        impl<T> std::error::Error for BorrowError<T> {}
+
+        /// Only available with the `defmt` feature.
+        #[cfg(feature = "defmt")]
+        impl<T> defmt::Format for BorrowError<T> {
+            fn format(&self, f: defmt::Formatter<'_>) {
+                defmt::write!(f, "Already borrowed")
+            }
+        }
+
+        /// Error returned by [`ThinCell::downcast`] when downcasting fails.
+        #[derive(Debug)]
+        pub enum DowncastError<T: ?Sized> {
+            /// The [`ThinCell`] is currently borrowed.
+            Borrowed(ThinCell<T>),
+
+            /// The inner value is not of the target type.
+            Type(ThinCell<T>),
+        }
+
+        impl<T: ?Sized> DowncastError<T> {
+            /// Consumes the error and returns the original `ThinCell<T>`.
+            pub fn into_inner(self) -> ThinCell<T> {
+                match self {
+                    DowncastError::Borrowed(cell) | DowncastError::Type(cell) => cell,
+                }
+            }
+        }
+
+        impl<T: Any + ?Sized> ThinCell<T> {
+            /// Attempts to downcast the `ThinCell<T>` to `ThinCell<U>`.
+            ///
+            /// # Returns
+            ///
+            /// - `Ok(ThinCell<U>)` if the inner value is of type `U` and is not
+            ///   currently borrowed
+            /// - `Err(DowncastError::Borrowed(self))` if the inner value is currently
+            ///   borrowed
+            /// - `Err(DowncastError::Type(self))` if the inner value is not of type `U`
+            pub fn downcast<U: Any>(self) -> Result<ThinCell<U>, DowncastError<T>> {
+                let inner = self.inner();
+                if !inner.state.try_borrow() {
+                    return Err(DowncastError::Borrowed(self));
+                }
+
+                // SAFETY: We have exclusive access via borrow flag.
+                let data_ref = unsafe { &*inner.data.get() };
+                let type_id = data_ref.type_id();
+                inner.state.unborrow();
+
+                if TypeId::of::<U>() == type_id {
+                    // SAFETY: We have verified that the inner value is of type `U`
+                    Ok(unsafe { self.downcast_unchecked::<U>() })
+                } else {
+                    Err(DowncastError::Type(self))
+                }
+            }
+        }
+
+        /// `ThinCell` is `Unpin` as it does not move its inner data.
+        impl<T: ?Sized> Unpin for ThinCell<T> {}
+
+        impl<'a, T: ?Sized> Ref<'a, T> {
+            /// Creates a guard releasing the borrow flag of `inner` on drop.
+            ///
+            /// # Safety
+            ///
+            /// The borrow flag must have just been set by the caller.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            unsafe fn new(inner: &'a Inner<T>) -> Self {
+                #[cfg(feature = "track-borrow")]
+                inner.state.track(std::panic::Location::caller());
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = std::ptr::from_ref(inner).addr(), "borrow");
+
+                Ref {
+                    // SAFETY: We have exclusive access via borrow flag and block further
+                    // access with `Ordering::Acquire`/`Release` pair.
+                    value: unsafe { &mut *inner.data.get() },
+                    state: &inner.state,
+                }
+            }
+
+            /// Makes a new `Ref` for a part of the borrowed value, e.g. a field.
+            ///
+            /// The borrow of the cell is kept until the returned guard is dropped.
+            ///
+            /// This is an associated function that needs to be used as `Ref::map(...)`,
+            /// as a method would interfere with methods of the same name on `T`.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::{Ref, ThinCell};
+            /// let cell = ThinCell::new((1, String::from("a")));
+            ///
+            /// let mut name = Ref::map(cell.borrow(), |v| &mut v.1);
+            /// name.push('b');
+            /// assert!(cell.try_borrow().is_none());
+            /// drop(name);
+            ///
+            /// assert_eq!(cell.borrow().1, "ab");
+            /// ```
+            pub fn map<U: ?Sized>(mut orig: Self, f: impl FnOnce(&mut T) -> &mut U) -> Ref<'a, U> {
+                // If `f` panics, `orig` releases the borrow as usual
+                let value: *mut U = f(&mut *orig);
+                let state = orig.state;
+                std::mem::forget(orig);
+
+                Ref {
+                    // SAFETY: `value` borrows from the value of the cell, whose borrow is
+                    // moved from `orig` to the new guard.
+                    value: unsafe { &mut *value },
+                    state,
+                }
+            }
+
+            /// Attaches a hook to the guard, run with the final value right before
+            /// the borrow is released.
+            ///
+            /// This ties validation or notification logic to the end of a mutation,
+            /// without wrapping `T`. The hook also runs if the guard is dropped while
+            /// unwinding, and the borrow is released even if the hook panics.
+            ///
+            /// This is an associated function that needs to be used as
+            /// `Ref::on_drop(...)`, like [`Ref::map`].
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::{Ref, ThinCell};
+            /// let cell = ThinCell::new(vec![3, 1]);
+            ///
+            /// let mut value = Ref::on_drop(cell.borrow(), |v: &Vec<i32>| {
+            ///     assert!(v.is_sorted(), "left unsorted");
+            /// });
+            /// value.push(2);
+            /// value.sort();
+            /// drop(value);
+            ///
+            /// assert_eq!(*cell.borrow(), [1, 2, 3]);
+            /// ```
+            pub fn on_drop<F: FnOnce(&T)>(orig: Self, f: F) -> HookedRef<'a, T, F> {
+                HookedRef {
+                    hook: Some(f),
+                    guard: orig,
+                }
+            }
+        }
+
+        /// A [`Ref`] running a hook when dropped, returned by [`Ref::on_drop`].
+        pub struct HookedRef<'a, T: ?Sized, F: FnOnce(&T)> {
+            hook: Option<F>,
+            guard: Ref<'a, T>,
+        }
+
+        impl<'a, T: ?Sized, F: FnOnce(&T)> Drop for HookedRef<'a, T, F> {
+            fn drop(&mut self) {
+                // `guard` is dropped after this, even if the hook panics
+                if let Some(hook) = self.hook.take() {
+                    hook(&self.guard);
+                }
+            }
+        }
+
+        impl<'a, T: ?Sized, F: FnOnce(&T)> Deref for HookedRef<'a, T, F> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.guard
+            }
+        }
+
+        impl<'a, T: ?Sized, F: FnOnce(&T)> DerefMut for HookedRef<'a, T, F> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.guard
+            }
+        }
+
+        impl<'a, T: Debug + ?Sized, F: FnOnce(&T)> Debug for HookedRef<'a, T, F> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&*self.guard, f)
+            }
+        }
+
+        /// A mutable guard returned by [`ThinCell::borrow_debug_checked`].
+        ///
+        /// With `debug_assertions`, this holds a [`Ref`]. Otherwise it holds the
+        /// value only and leaves the borrow flag alone when dropped, as it was
+        /// never set.
+        pub struct DebugCheckedRef<'a, T: ?Sized> {
+            #[cfg(debug_assertions)]
+            guard: Ref<'a, T>,
+            #[cfg(not(debug_assertions))]
+            value: &'a mut T,
+        }
+
+        impl<'a, T: ?Sized> Deref for DebugCheckedRef<'a, T> {
+            type Target = T;
+
+            #[cfg(debug_assertions)]
+            fn deref(&self) -> &T {
+                &self.guard
+            }
+
+            #[cfg(not(debug_assertions))]
+            fn deref(&self) -> &T {
+                self.value
+            }
+        }
+
+        impl<'a, T: ?Sized> DerefMut for DebugCheckedRef<'a, T> {
+            #[cfg(debug_assertions)]
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.guard
+            }
+
+            #[cfg(not(debug_assertions))]
+            fn deref_mut(&mut self) -> &mut T {
+                self.value
+            }
+        }
+
+        impl<'a, T: Debug + ?Sized> Debug for DebugCheckedRef<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&**self, f)
+            }
+        }
+
+        impl<'a, T: ?Sized> Drop for Ref<'a, T> {
+            fn drop(&mut self) {
+                #[cfg(feature = "track-borrow")]
+                self.state.untrack();
+
+                self.state.unborrow();
+            }
+        }
+
+        impl<'a, T: ?Sized> Deref for Ref<'a, T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                self.value
+            }
+        }
+
+        impl<'a, T: ?Sized> DerefMut for Ref<'a, T> {
+            fn deref_mut(&mut self) -> &mut T {
+                self.value
+            }
+        }
+
+        impl<'a, T: Debug + ?Sized> Debug for Ref<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&**self, f)
+            }
+        }
+
+        impl<'a, T: Display + ?Sized> Display for Ref<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Display::fmt(&**self, f)
+            }
+        }
+
+        /// Only available with the `defmt` feature.
+        #[cfg(feature = "defmt")]
+        impl<'a, T: defmt::Format + ?Sized> defmt::Format for Ref<'a, T> {
+            fn format(&self, f: defmt::Formatter<'_>) {
+                defmt::Format::format(&**self, f)
+            }
+        }
+
+        impl<'a, T: ?Sized> AsRef<T> for Ref<'a, T> {
+            fn as_ref(&self) -> &T {
+                self.value
+            }
+        }
+
+        impl<'a, T: ?Sized> AsMut<T> for Ref<'a, T> {
+            fn as_mut(&mut self) -> &mut T {
+                self.value
+            }
+        }
+
+        impl<'a, T: ?Sized> std::borrow::Borrow<T> for Ref<'a, T> {
+            fn borrow(&self) -> &T {
+                self.value
+            }
+        }
+
+        impl<'a, T: ?Sized> std::borrow::BorrowMut<T> for Ref<'a, T> {
+            fn borrow_mut(&mut self) -> &mut T {
+                self.value
+            }
+        }
+
+        /// A mutable guard owning a handle of its cell, returned by
+        /// [`ThinCell::borrow_owned`].
+        ///
+        /// Unlike [`Ref`], it doesn't borrow a handle, so it can be stored in other
+        /// structs or moved into callbacks. [`OwnedRef::map`] narrows it to a part
+        /// `U` of the value `T` of the cell.
+        pub struct OwnedRef<T: ?Sized, U: ?Sized = T> {
+            value: NonNull<U>,
+            cell: ThinCell<T>,
+        }
+
+        impl<T: ?Sized> ThinCell<T> {
+            /// Borrows the value mutably, returning a guard that owns this handle.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the value is already
+            /// borrowed, see [`ThinCell::borrow`].
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::{OwnedRef, ThinCell};
+            /// let cell = ThinCell::new((1, String::from("a")));
+            ///
+            /// let mut name = OwnedRef::map(cell.clone().borrow_owned(), |v| &mut v.1);
+            /// let mut callback = move || name.push('b');
+            /// callback();
+            /// drop(callback);
+            ///
+            /// assert_eq!(cell.borrow().1, "ab");
+            /// ```
+            #[track_caller]
+            pub fn borrow_owned(self) -> OwnedRef<T> {
+                #[cfg(debug_assertions)]
+                self.check_poison();
+
+                self.state().borrow();
+                // SAFETY: The borrow flag was just set
+                unsafe { OwnedRef::new(self) }
+            }
+
+            /// Tries to borrow the value mutably, returning a guard that owns this
+            /// handle, or `Err(self)` if it is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow_owned(self) -> Result<OwnedRef<T>, Self> {
+                if !self.state().try_borrow() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(addr = self.addr(), "borrow conflict");
+                    return Err(self);
+                }
+
+                // SAFETY: The borrow flag was just set
+                Ok(unsafe { OwnedRef::new(self) })
+            }
+        }
+
+        impl<T: ?Sized> OwnedRef<T> {
+            /// Creates a guard releasing the borrow flag of `cell` on drop.
+            ///
+            /// # Safety
+            ///
+            /// The borrow flag must have just been set by the caller.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            unsafe fn new(cell: ThinCell<T>) -> Self {
+                let inner = cell.inner();
+
+                #[cfg(feature = "track-borrow")]
+                inner.state.track(std::panic::Location::caller());
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = cell.addr(), "borrow");
+
+                OwnedRef {
+                    // SAFETY: `UnsafeCell::get` never returns null
+                    value: unsafe { NonNull::new_unchecked(inner.data.get()) },
+                    cell,
+                }
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> OwnedRef<T, U> {
+            /// Makes a new `OwnedRef` for a part of the borrowed value, e.g. a field.
+            ///
+            /// The borrow of the cell is kept until the returned guard is dropped.
+            /// This is an associated function that needs to be used as
+            /// `OwnedRef::map(...)`, like [`Ref::map`].
+            pub fn map<V: ?Sized>(
+                mut this: Self,
+                f: impl FnOnce(&mut U) -> &mut V,
+            ) -> OwnedRef<T, V> {
+                // If `f` panics, `this` releases the borrow as usual
+                let value = NonNull::from(f(&mut *this));
+                Self::with_value(this, value)
+            }
+
+            /// Makes a new `OwnedRef` for an optional part of the borrowed value, or
+            /// returns the original guard if `f` returns `None`.
+            ///
+            /// This is an associated function that needs to be used as
+            /// `OwnedRef::try_map(...)`.
+            pub fn try_map<V: ?Sized>(
+                mut this: Self,
+                f: impl FnOnce(&mut U) -> Option<&mut V>,
+            ) -> Result<OwnedRef<T, V>, Self> {
+                match f(&mut *this).map(NonNull::from) {
+                    Some(value) => Ok(Self::with_value(this, value)),
+                    None => Err(this),
+                }
+            }
+
+            /// Moves the borrow of `this` to a guard of `value`.
+            fn with_value<V: ?Sized>(this: Self, value: NonNull<V>) -> OwnedRef<T, V> {
+                let this = ManuallyDrop::new(this);
+                OwnedRef {
+                    value,
+                    // SAFETY: `this` is not dropped, so the handle is moved out once
+                    cell: unsafe { std::ptr::read(&this.cell) },
+                }
+            }
+
+            /// Returns the cell of the guard.
+            ///
+            /// This is an associated function that needs to be used as
+            /// `OwnedRef::cell(...)`.
+            pub fn cell(this: &Self) -> &ThinCell<T> {
+                &this.cell
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> Drop for OwnedRef<T, U> {
+            fn drop(&mut self) {
+                #[cfg(feature = "track-borrow")]
+                self.cell.state().untrack();
+
+                self.cell.state().unborrow();
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> Deref for OwnedRef<T, U> {
+            type Target = U;
+
+            fn deref(&self) -> &U {
+                // SAFETY: The value is borrowed through the flag of the owned cell
+                unsafe { self.value.as_ref() }
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> DerefMut for OwnedRef<T, U> {
+            fn deref_mut(&mut self) -> &mut U {
+                // SAFETY: The value is borrowed through the flag of the owned cell
+                unsafe { self.value.as_mut() }
+            }
+        }
+
+        impl<T: ?Sized, U: Debug + ?Sized> Debug for OwnedRef<T, U> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&**self, f)
+            }
+        }
+
+        impl<T: ?Sized, U: Display + ?Sized> Display for OwnedRef<T, U> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Display::fmt(&**self, f)
+            }
+        }
+
+        impl<T: ?Sized> Clone for ThinCell<T> {
+            fn clone(&self) -> Self {
+                self.state().inc();
+
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = self.addr(), count = self.count(), "clone");
+
+                ThinCell {
+                    ptr: self.ptr,
+                    _marker: PhantomData,
+                }
+            }
+        }
+
+        impl<T: ?Sized> ThinCell<T> {
+            /// Releases this owner, dropping the value if it is the last one.
+            ///
+            /// Does not access `T` other than to drop it, see the `nightly` impl of
+            /// `Drop`.
+            fn release(&mut self) {
+                // Other owners may free the cell as soon as this one is released
+                #[cfg(feature = "tracing")]
+                tracing::trace!(addr = self.addr(), count = self.count() - 1, "drop");
+
+                let inner = self.inner();
+                if !inner.state.dec() {
+                    // Not last owner, nothing to do
+                    return;
+                }
+
+                // SAFETY: We are the last owner, so we have unique ownership, and we're not
+                // using `self` after this call.
+                unsafe {
+                    self.drop_in_place();
+                }
+            }
+        }
+
+        #[cfg(not(feature = "nightly"))]
+        impl<T: ?Sized> Drop for ThinCell<T> {
+            fn drop(&mut self) {
+                self.release()
+            }
+        }
+
+        // SAFETY: Like `Rc`, dropping a `ThinCell` never accesses `T` except for
+        // dropping it, which `PhantomData<Inner<T>>` tells the drop checker about.
+        #[cfg(feature = "nightly")]
+        unsafe impl<#[may_dangle] T: ?Sized> Drop for ThinCell<T> {
+            fn drop(&mut self) {
+                self.release()
+            }
+        }
+
+        #[cfg(feature = "debug")]
+        impl<T: crate::debug::Trace + ?Sized> crate::debug::Trace for ThinCell<T> {
+            fn trace(&self, tracer: &mut crate::debug::Tracer) {
+                let addr = self.addr();
+                if tracer.visit(addr, self.count()) {
+                    if let Some(value) = self.try_borrow() {
+                        tracer.enter(addr, &*value);
+                    }
+                }
+            }
+        }
+
+        impl<T: Default> Default for ThinCell<T> {
+            fn default() -> Self {
+                ThinCell::new(T::default())
+            }
+        }
+
+        impl<T: Debug + ?Sized> Debug for ThinCell<T> {
+            /// Formats the value and state of the cell.
+            ///
+            /// The alternate form (`{:#?}`) lists the address, type, owner count and
+            /// borrow flag of the allocation instead of the raw state, which helps
+            /// telling which handles are shared when dumping a graph of them.
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let inner = self.inner();
+                let state = inner.state.load();
+                let alternate = f.alternate();
+                let mut d = f.debug_struct("ThinCell");
+                if alternate {
+                    d.field("addr", &format_args!("{:#x}", self.addr()))
+                        .field("type", &format_args!("{}", std::any::type_name::<T>()))
+                        .field("count", &state.count())
+                        .field("borrowed", &state.is_borrowed());
+                    return match self.try_borrow() {
+                        Some(borrowed) => d.field("value", &borrowed),
+                        None => d.field("value", &format_args!("<borrowed>")),
+                    }
+                    .finish();
+                }
+                match self.try_borrow() {
+                    Some(borrowed) => d.field("value", &borrowed),
+                    None => d.field("value", &format_args!("<borrowed>")),
+                }
+                .field("state", &state)
+                .finish()
+            }
+        }
+
+        impl<T: Display + ?Sized> Display for ThinCell<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.try_borrow() {
+                    Some(borrowed) => Display::fmt(&*borrowed, f),
+                    None => write!(f, "<borrowed>"),
+                }
+            }
+        }
+
+        /// Formats the value, or `<borrowed>` if it is currently borrowed, like the
+        /// `Display` impl. Only available with the `defmt` feature.
+        #[cfg(feature = "defmt")]
+        impl<T: defmt::Format + ?Sized> defmt::Format for ThinCell<T> {
+            fn format(&self, f: defmt::Formatter<'_>) {
+                match self.try_borrow() {
+                    Some(borrowed) => defmt::Format::format(&*borrowed, f),
+                    None => defmt::write!(f, "<borrowed>"),
+                }
+            }
+        }
+
+        impl<T: ?Sized> ThinCell<T> {
+            /// Borrows the value for a single I/O call, failing with
+            /// `ErrorKind::ResourceBusy` instead of panicking or blocking.
+            fn io_borrow(&self) -> std::io::Result<Ref<'_, T>> {
+                self.try_borrow().ok_or_else(|| {
+                    let kind = std::io::ErrorKind::ResourceBusy;
+                    std::io::Error::new(kind, BorrowError { value: () })
+                })
+            }
+        }
+
+        /// Reads from the value, borrowing it for each call only.
+        ///
+        /// Calls fail with [`ErrorKind::ResourceBusy`](std::io::ErrorKind::ResourceBusy),
+        /// wrapping a [`BorrowError`], if the value is already borrowed.
+        impl<T: std::io::Read + ?Sized> std::io::Read for &ThinCell<T> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.io_borrow()?.read(buf)
+            }
+
+            fn read_vectored(
+                &mut self,
+                bufs: &mut [std::io::IoSliceMut<'_>],
+            ) -> std::io::Result<usize> {
+                self.io_borrow()?.read_vectored(bufs)
+            }
+
+            fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+                self.io_borrow()?.read_to_end(buf)
+            }
+
+            fn read_to_string(&mut self, buf: &mut String) -> std::io::Result<usize> {
+                self.io_borrow()?.read_to_string(buf)
+            }
+
+            fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+                self.io_borrow()?.read_exact(buf)
+            }
+        }
+
+        /// Writes to the value, borrowing it for each call only.
+        ///
+        /// Calls fail with [`ErrorKind::ResourceBusy`](std::io::ErrorKind::ResourceBusy),
+        /// wrapping a [`BorrowError`], if the value is already borrowed.
+        impl<T: std::io::Write + ?Sized> std::io::Write for &ThinCell<T> {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.io_borrow()?.write(buf)
+            }
+
+            fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+                self.io_borrow()?.write_vectored(bufs)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.io_borrow()?.flush()
+            }
+
+            fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+                self.io_borrow()?.write_all(buf)
+            }
+        }
+
+        /// Seeks in the value, borrowing it for each call only.
+        ///
+        /// Calls fail with [`ErrorKind::ResourceBusy`](std::io::ErrorKind::ResourceBusy),
+        /// wrapping a [`BorrowError`], if the value is already borrowed.
+        impl<T: std::io::Seek + ?Sized> std::io::Seek for &ThinCell<T> {
+            fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                self.io_borrow()?.seek(pos)
+            }
+
+            fn stream_position(&mut self) -> std::io::Result<u64> {
+                self.io_borrow()?.stream_position()
+            }
+        }
+
+        #[cfg(feature = "futures-io")]
+        impl<T: Unpin + ?Sized> ThinCell<T> {
+            /// Polls the value for a single async I/O call, see
+            /// [`io_borrow`](ThinCell::io_borrow).
+            fn poll_io<R>(
+                &self,
+                poll: impl FnOnce(Pin<&mut T>) -> Poll<std::io::Result<R>>,
+            ) -> Poll<std::io::Result<R>> {
+                match self.io_borrow() {
+                    Ok(mut value) => poll(Pin::new(&mut *value)),
+                    Err(e) => Poll::Ready(Err(e)),
+                }
+            }
+        }
+
+        /// Reads from the value, borrowing it for each poll only.
+        ///
+        /// Polls fail with [`ErrorKind::ResourceBusy`](std::io::ErrorKind::ResourceBusy),
+        /// wrapping a [`BorrowError`], if the value is already borrowed. Only
+        /// available with the `futures-io` feature.
+        #[cfg(feature = "futures-io")]
+        impl<T: futures_io::AsyncRead + Unpin + ?Sized> futures_io::AsyncRead for &ThinCell<T> {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<std::io::Result<usize>> {
+                self.poll_io(|value| value.poll_read(cx, buf))
+            }
+
+            fn poll_read_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &mut [std::io::IoSliceMut<'_>],
+            ) -> Poll<std::io::Result<usize>> {
+                self.poll_io(|value| value.poll_read_vectored(cx, bufs))
+            }
+        }
+
+        /// Reads from the value like `&ThinCell<T>` does.
+        #[cfg(feature = "futures-io")]
+        impl<T: futures_io::AsyncRead + Unpin + ?Sized> futures_io::AsyncRead for ThinCell<T> {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<std::io::Result<usize>> {
+                Pin::new(&mut &*self).poll_read(cx, buf)
+            }
+
+            fn poll_read_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &mut [std::io::IoSliceMut<'_>],
+            ) -> Poll<std::io::Result<usize>> {
+                Pin::new(&mut &*self).poll_read_vectored(cx, bufs)
+            }
+        }
+
+        /// Writes to the value, borrowing it for each poll only.
+        ///
+        /// Polls fail with [`ErrorKind::ResourceBusy`](std::io::ErrorKind::ResourceBusy),
+        /// wrapping a [`BorrowError`], if the value is already borrowed. Only
+        /// available with the `futures-io` feature.
+        #[cfg(feature = "futures-io")]
+        impl<T: futures_io::AsyncWrite + Unpin + ?Sized> futures_io::AsyncWrite for &ThinCell<T> {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                self.poll_io(|value| value.poll_write(cx, buf))
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[std::io::IoSlice<'_>],
+            ) -> Poll<std::io::Result<usize>> {
+                self.poll_io(|value| value.poll_write_vectored(cx, bufs))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                self.poll_io(|value| value.poll_flush(cx))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                self.poll_io(|value| value.poll_close(cx))
+            }
+        }
+
+        /// Writes to the value like `&ThinCell<T>` does.
+        #[cfg(feature = "futures-io")]
+        impl<T: futures_io::AsyncWrite + Unpin + ?Sized> futures_io::AsyncWrite for ThinCell<T> {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                Pin::new(&mut &*self).poll_write(cx, buf)
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[std::io::IoSlice<'_>],
+            ) -> Poll<std::io::Result<usize>> {
+                Pin::new(&mut &*self).poll_write_vectored(cx, bufs)
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut &*self).poll_flush(cx)
+            }
+
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut &*self).poll_close(cx)
+            }
+        }
+
+        /// Returns the raw descriptor of the value, borrowing it for the read only.
+        ///
+        /// # Panics
+        ///
+        /// Panics (or blocks for the `sync` flavour) if the value is already
+        /// borrowed. The descriptor may be closed through another handle once this
+        /// returns, use the [`AsFd`](std::os::fd::AsFd) impl of a [`Ref`] to keep
+        /// it borrowed.
+        #[cfg(unix)]
+        impl<T: std::os::fd::AsRawFd + ?Sized> std::os::fd::AsRawFd for ThinCell<T> {
+            #[track_caller]
+            fn as_raw_fd(&self) -> std::os::fd::RawFd {
+                self.borrow().as_raw_fd()
+            }
+        }
+
+        #[cfg(unix)]
+        impl<'a, T: std::os::fd::AsRawFd + ?Sized> std::os::fd::AsRawFd for Ref<'a, T> {
+            fn as_raw_fd(&self) -> std::os::fd::RawFd {
+                (**self).as_raw_fd()
+            }
+        }
+
+        #[cfg(unix)]
+        impl<'a, T: std::os::fd::AsFd + ?Sized> std::os::fd::AsFd for Ref<'a, T> {
+            fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+                (**self).as_fd()
+            }
+        }
+
+        /// Returns the raw handle of the value, borrowing it for the read only.
+        ///
+        /// # Panics
+        ///
+        /// Panics (or blocks for the `sync` flavour) if the value is already
+        /// borrowed. The handle may be closed through another handle once this
+        /// returns, use the [`AsHandle`](std::os::windows::io::AsHandle) impl of a
+        /// [`Ref`] to keep it borrowed.
+        #[cfg(windows)]
+        impl<T: std::os::windows::io::AsRawHandle + ?Sized> std::os::windows::io::AsRawHandle
+            for ThinCell<T>
+        {
+            #[track_caller]
+            fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+                self.borrow().as_raw_handle()
+            }
+        }
+
+        /// Returns the raw socket of the value, borrowing it for the read only.
+        ///
+        /// # Panics
+        ///
+        /// Panics (or blocks for the `sync` flavour) if the value is already
+        /// borrowed. The socket may be closed through another handle once this
+        /// returns, use the [`AsSocket`](std::os::windows::io::AsSocket) impl of a
+        /// [`Ref`] to keep it borrowed.
+        #[cfg(windows)]
+        impl<T: std::os::windows::io::AsRawSocket + ?Sized> std::os::windows::io::AsRawSocket
+            for ThinCell<T>
+        {
+            #[track_caller]
+            fn as_raw_socket(&self) -> std::os::windows::io::RawSocket {
+                self.borrow().as_raw_socket()
+            }
+        }
+
+        #[cfg(windows)]
+        impl<'a, T: std::os::windows::io::AsRawHandle + ?Sized> std::os::windows::io::AsRawHandle
+            for Ref<'a, T>
+        {
+            fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+                (**self).as_raw_handle()
+            }
+        }
+
+        #[cfg(windows)]
+        impl<'a, T: std::os::windows::io::AsHandle + ?Sized> std::os::windows::io::AsHandle
+            for Ref<'a, T>
+        {
+            fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+                (**self).as_handle()
+            }
+        }
+
+        #[cfg(windows)]
+        impl<'a, T: std::os::windows::io::AsRawSocket + ?Sized> std::os::windows::io::AsRawSocket
+            for Ref<'a, T>
+        {
+            fn as_raw_socket(&self) -> std::os::windows::io::RawSocket {
+                (**self).as_raw_socket()
+            }
+        }
+
+        #[cfg(windows)]
+        impl<'a, T: std::os::windows::io::AsSocket + ?Sized> std::os::windows::io::AsSocket
+            for Ref<'a, T>
+        {
+            fn as_socket(&self) -> std::os::windows::io::BorrowedSocket<'_> {
+                (**self).as_socket()
+            }
+        }
+
+        impl<T: PartialEq + ?Sized> ThinCell<T> {
+            /// Compares the values of two cells without panicking or blocking.
+            ///
+            /// Returns `None` if either cell is currently borrowed. Handles of the
+            /// same cell borrow it once, and still compare its value with itself, so
+            /// e.g. a cell holding `f64::NAN` is not equal to itself.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use thin_cell::unsync::ThinCell;
+            /// let a = ThinCell::new(1);
+            /// let b = ThinCell::new(1);
+            /// assert_eq!(a.value_eq(&b), Some(true));
+            ///
+            /// let guard = a.borrow();
+            /// assert_eq!(a.value_eq(&b), None);
+            /// assert_eq!(a.value_eq(&a.clone()), None);
+            /// drop(guard);
+            ///
+            /// let nan = ThinCell::new(f64::NAN);
+            /// assert_eq!(nan.value_eq(&nan.clone()), Some(false));
+            /// ```
+            pub fn value_eq(&self, other: &Self) -> Option<bool> {
+                let value = self.try_borrow()?;
+                if self.ptr_eq(other) {
+                    return Some(*value == *value);
                 }
+                Some(*value == *other.try_borrow()?)
             }
         }
 
@@ -0,0 +1,76 @@
+/// Creates a `ThinCell<[T]>` from a list of elements, like `vec!`.
+///
+/// The elements are moved into a single allocation holding the cell header.
+/// With `thin_slice![elem; n]`, `elem` is cloned `n` times and `n` must be a
+/// constant.
+/// The cell is an `unsync::ThinCell` unless the list is prefixed with
+/// `sync:`.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{sync, thin_slice, unsync};
+///
+/// let cell: unsync::ThinCell<[i32]> = thin_slice![1, 2, 3];
+/// assert_eq!(*cell.borrow(), [1, 2, 3]);
+///
+/// let cell: sync::ThinCell<[String]> = thin_slice![sync: String::new(); 2];
+/// assert_eq!(cell.borrow().len(), 2);
+/// ```
+#[macro_export]
+macro_rules! thin_slice {
+    (sync: $($rest:tt)*) => {
+        $crate::thin_slice!(@sync $($rest)*)
+    };
+    (unsync: $($rest:tt)*) => {
+        $crate::thin_slice!(@unsync $($rest)*)
+    };
+    (@$flavour:ident $elem:expr; $n:expr) => {{
+        let elem = $elem;
+        $crate::$flavour::ThinCell::new(::std::array::from_fn::<_, { $n }, _>(|_| {
+            ::std::clone::Clone::clone(&elem)
+        }))
+        .unsize_slice()
+    }};
+    (@$flavour:ident $($x:expr),* $(,)?) => {
+        $crate::$flavour::ThinCell::new([$($x),*]).unsize_slice()
+    };
+    ($($rest:tt)*) => {
+        $crate::thin_slice!(@unsync $($rest)*)
+    };
+}
+
+/// Creates a `ThinCell<str>` from a constant string, e.g. a literal.
+///
+/// The bytes are copied into a single allocation holding the cell header.
+/// The cell is an `unsync::ThinCell` unless the string is prefixed with
+/// `sync:`.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{sync, thin_str, unsync};
+///
+/// let cell: unsync::ThinCell<str> = thin_str!("hello");
+/// cell.borrow().make_ascii_uppercase();
+/// assert_eq!(&*cell.borrow(), "HELLO");
+///
+/// const NAME: &str = "thin";
+/// let cell: sync::ThinCell<str> = thin_str!(sync: NAME);
+/// assert_eq!(&*cell.borrow(), "thin");
+/// ```
+#[macro_export]
+macro_rules! thin_str {
+    (sync: $s:expr) => {
+        $crate::thin_str!(@sync $s)
+    };
+    (unsync: $s:expr) => {
+        $crate::thin_str!(@unsync $s)
+    };
+    (@$flavour:ident $s:expr) => {
+        $crate::$flavour::ThinCell::<str>::__from_str::<{ $s.len() }>($s)
+    };
+    ($s:expr) => {
+        $crate::thin_str!(@unsync $s)
+    };
+}
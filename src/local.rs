@@ -0,0 +1,43 @@
+/// Declares thread-local `ThinCell`s, like `thread_local!` with a `RefCell`.
+///
+/// Each thread lazily creates its own `unsync::ThinCell` from the initializer
+/// on first access. The cell is reached through [`LocalKey::with`], and can be
+/// cloned out of the closure to keep a handle of the per-thread value.
+///
+/// [`LocalKey::with`]: std::thread::LocalKey::with
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::thread_local_cell;
+///
+/// thread_local_cell! {
+///     static COUNTER: u32 = 0;
+///     pub static NAMES: Vec<String> = Vec::new();
+/// }
+///
+/// COUNTER.with(|cell| *cell.borrow() += 1);
+/// let handle = COUNTER.with(|cell| cell.clone());
+/// assert_eq!(*handle.borrow(), 1);
+///
+/// std::thread::spawn(|| COUNTER.with(|cell| assert_eq!(*cell.borrow(), 0)))
+///     .join()
+///     .unwrap();
+/// ```
+#[macro_export]
+macro_rules! thread_local_cell {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        ::std::thread_local! {
+            $(#[$attr])* $vis static $name: $crate::unsync::ThinCell<$t> =
+                $crate::unsync::ThinCell::new($init);
+        }
+        $crate::thread_local_cell!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        ::std::thread_local! {
+            $(#[$attr])* $vis static $name: $crate::unsync::ThinCell<$t> =
+                $crate::unsync::ThinCell::new($init);
+        }
+    };
+}
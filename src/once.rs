@@ -0,0 +1,224 @@
+macro_rules! thin_once {
+    ($once:ident, $lazy:ident) => {
+        /// A shared cell which can be written to only once, like a reference
+        /// counted `OnceCell`.
+        ///
+        /// It shares the thin header of [`ThinCell`], but once initialized the
+        /// value is accessed through plain `&T` without touching the borrow flag.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::OnceThinCell;
+        /// let cell = OnceThinCell::new();
+        /// let other = cell.clone();
+        /// assert!(cell.get().is_none());
+        ///
+        /// let value = other.get_or_init(|| String::from("init"));
+        /// assert_eq!(value, "init");
+        /// assert_eq!(cell.set(String::from("again")), Err(String::from("again")));
+        /// assert_eq!(cell.get().map(String::as_str), Some("init"));
+        /// ```
+        pub struct OnceThinCell<T> {
+            // The value is never borrowed mutably through `cell`, so shared
+            // references to it can be handed out freely.
+            cell: ThinCell<$once<T>>,
+        }
+
+        impl<T> OnceThinCell<T> {
+            /// Creates a new uninitialized cell.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new() -> Self {
+                OnceThinCell {
+                    cell: ThinCell::new($once::new()),
+                }
+            }
+
+            fn once(&self) -> &$once<T> {
+                // SAFETY: No mutable reference to the value is ever created while
+                // the cell is shared, see `cell`.
+                unsafe { &*self.cell.inner().data.get() }
+            }
+
+            /// Returns the value, or `None` if the cell is uninitialized.
+            pub fn get(&self) -> Option<&T> {
+                self.once().get()
+            }
+
+            /// Initializes the cell with `value`.
+            ///
+            /// Returns `Err(value)` if the cell was already initialized.
+            pub fn set(&self, value: T) -> Result<(), T> {
+                self.once().set(value)
+            }
+
+            /// Returns the value, initializing the cell with `f` first if it is
+            /// uninitialized.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `f` panics, leaving the cell uninitialized, or if `f`
+            /// initializes the cell itself.
+            pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+                self.once().get_or_init(f)
+            }
+
+            /// Consumes the handle and returns the value if there are no other
+            /// owners, or `Err(self)` otherwise.
+            pub fn try_unwrap(self) -> Result<Option<T>, Self> {
+                self.cell
+                    .try_unwrap()
+                    .map($once::into_inner)
+                    .map_err(|cell| OnceThinCell { cell })
+            }
+
+            /// Returns the number of owners.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles point to the same allocation.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl<T> Clone for OnceThinCell<T> {
+            fn clone(&self) -> Self {
+                OnceThinCell {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl<T> Default for OnceThinCell<T> {
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T> From<T> for OnceThinCell<T> {
+            /// Creates an initialized cell.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn from(value: T) -> Self {
+                OnceThinCell {
+                    cell: ThinCell::new($once::from(value)),
+                }
+            }
+        }
+
+        impl<T: Debug> Debug for OnceThinCell<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut d = f.debug_tuple("OnceThinCell");
+                match self.get() {
+                    Some(value) => d.field(value),
+                    None => d.field(&format_args!("<uninit>")),
+                }
+                .finish()
+            }
+        }
+
+        /// A shared value which is initialized on first access, like a reference
+        /// counted `LazyCell`.
+        ///
+        /// The initializer is stored in the cell until the value is first
+        /// dereferenced by any of the handles, so an expensive resource can be
+        /// declared up front and only built if needed. Like [`OnceThinCell`],
+        /// the value is then accessed without touching the borrow flag.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::LazyThinCell;
+        /// use std::cell::Cell;
+        ///
+        /// let calls = Cell::new(0);
+        /// let lazy = LazyThinCell::new(|| {
+        ///     calls.set(calls.get() + 1);
+        ///     vec![1, 2, 3]
+        /// });
+        /// let other = lazy.clone();
+        /// assert_eq!(calls.get(), 0);
+        ///
+        /// assert_eq!(other.len(), 3);
+        /// assert_eq!(*lazy, [1, 2, 3]);
+        /// assert_eq!(calls.get(), 1);
+        /// ```
+        pub struct LazyThinCell<T, F = fn() -> T> {
+            // Never borrowed mutably, see `OnceThinCell`
+            cell: ThinCell<$lazy<T, F>>,
+        }
+
+        impl<T, F: FnOnce() -> T> LazyThinCell<T, F> {
+            /// Creates a new lazy cell initialized with `f` on first access.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new(f: F) -> Self {
+                LazyThinCell {
+                    cell: ThinCell::new($lazy::new(f)),
+                }
+            }
+
+            /// Forces the evaluation of the lazy value and returns a reference to it.
+            ///
+            /// This is equivalent to the `Deref` impl, but is explicit.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the initializer panicked, now or on an earlier access.
+            pub fn force(this: &Self) -> &T {
+                $lazy::force(this.lazy())
+            }
+        }
+
+        impl<T, F> LazyThinCell<T, F> {
+            fn lazy(&self) -> &$lazy<T, F> {
+                // SAFETY: No mutable reference to the value is ever created while
+                // the cell is shared, see `cell`.
+                unsafe { &*self.cell.inner().data.get() }
+            }
+
+            /// Returns the number of owners.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles point to the same allocation.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl<T, F: FnOnce() -> T> Deref for LazyThinCell<T, F> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                LazyThinCell::force(self)
+            }
+        }
+
+        impl<T, F> Clone for LazyThinCell<T, F> {
+            fn clone(&self) -> Self {
+                LazyThinCell {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl<T: Default> Default for LazyThinCell<T> {
+            /// Creates a new lazy cell initialized with `T::default()`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            fn default() -> Self {
+                Self::new(T::default)
+            }
+        }
+
+        impl<T: Debug, F> Debug for LazyThinCell<T, F> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple("LazyThinCell").field(self.lazy()).finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_once;
@@ -0,0 +1,137 @@
+macro_rules! thin_pool {
+    () => {
+        /// Slot of a [`ThinPool`], with room for the pointer to the pool right
+        /// before the `Inner`.
+        #[repr(C)]
+        struct PoolSlot<T> {
+            _prefix: MaybeUninit<NonNull<Release>>,
+            inner: MaybeUninit<Inner<T>>,
+        }
+
+        /// A fixed-capacity pool of cells, which can be declared as a `static` to
+        /// create cells without allocating.
+        ///
+        /// Cells created by [`try_new`](ThinPool::try_new) are regular one word
+        /// handles living in one of the `N` slots of the pool, and give it back
+        /// once the last owner is gone. This suits targets without a heap, or
+        /// bounding the memory used by a kind of cell.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use thin_cell::unsync::ThinPool;
+        ///
+        /// static POOL: ThinPool<u32, 2> = ThinPool::new();
+        ///
+        /// let a = POOL.try_new(1).unwrap();
+        /// let b = POOL.try_new(2).unwrap();
+        /// assert_eq!(POOL.try_new(3).unwrap_err(), 3);
+        ///
+        /// drop(a);
+        /// let c = POOL.try_new(3).unwrap();
+        /// assert_eq!(*b.borrow() + *c.borrow(), 5);
+        /// ```
+        #[repr(C)]
+        pub struct ThinPool<T, const N: usize> {
+            header: Release,
+            used: [std::sync::atomic::AtomicBool; N],
+            slots: [UnsafeCell<PoolSlot<T>>; N],
+        }
+
+        // SAFETY: Slots are claimed and released atomically, and values are only
+        // accessed through the cells created in them.
+        unsafe impl<T: Send, const N: usize> Sync for ThinPool<T, N> {}
+
+        impl<T, const N: usize> ThinPool<T, N> {
+            /// Creates a pool with `N` free slots.
+            pub const fn new() -> Self {
+                ThinPool {
+                    header: Release {
+                        release: Self::release,
+                    },
+                    used: [const { std::sync::atomic::AtomicBool::new(false) }; N],
+                    slots: [const {
+                        UnsafeCell::new(PoolSlot {
+                            _prefix: MaybeUninit::uninit(),
+                            inner: MaybeUninit::uninit(),
+                        })
+                    }; N],
+                }
+            }
+
+            unsafe fn release(header: NonNull<Release>, inner: NonNull<()>) {
+                // SAFETY: `header` points to the start of a live pool
+                let pool = unsafe { header.cast::<Self>().as_ref() };
+                let first = pool.slots.as_ptr().addr() + std::mem::offset_of!(PoolSlot<T>, inner);
+                let index = (inner.addr().get() - first) / size_of::<PoolSlot<T>>();
+                pool.used[index].store(false, std::sync::atomic::Ordering::Release);
+            }
+
+            /// Creates a cell holding `value` in a free slot, or returns
+            /// `Err(value)` if all slots are in use.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn try_new(&'static self, value: T) -> Result<ThinCell<T>, T> {
+                use std::sync::atomic::Ordering::{Acquire, Relaxed};
+
+                let Some(index) = self
+                    .used
+                    .iter()
+                    .position(|used| used.compare_exchange(false, true, Acquire, Relaxed).is_ok())
+                else {
+                    return Err(value);
+                };
+
+                // SAFETY: The slot was just claimed, and is handed back by `release`
+                // once the cell is gone.
+                let cell = unsafe {
+                    let ptr = NonNull::new_unchecked(self.slots[index].get())
+                        .byte_add(std::mem::offset_of!(PoolSlot<T>, inner))
+                        .cast::<()>();
+                    set_header(ptr, NonNull::from(self).cast());
+                    ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new_external(),
+                        data: UnsafeCell::new(value),
+                    });
+
+                    ThinCell {
+                        ptr,
+                        _marker: PhantomData,
+                    }
+                };
+                cell.register();
+                Ok(cell)
+            }
+
+            /// Returns the number of slots in use.
+            pub fn len(&self) -> usize {
+                self.used
+                    .iter()
+                    .filter(|used| used.load(std::sync::atomic::Ordering::Relaxed))
+                    .count()
+            }
+
+            /// Returns `true` if no slot is in use.
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+        }
+
+        impl<T, const N: usize> Default for ThinPool<T, N> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T, const N: usize> Debug for ThinPool<T, N> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("ThinPool")
+                    .field("len", &self.len())
+                    .field("capacity", &N)
+                    .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_pool;
@@ -0,0 +1,86 @@
+//! Hooks to attribute `ThinCell` allocations in heap profiles.
+//!
+//! Profilers such as `dhat` or heaptrack attribute memory to the call stack
+//! of the allocation, which for cells is always inside this crate. A
+//! [`Profiler`] installed with [`set_profiler`] is told about every allocation
+//! with the type of its value and where the cell was created, so that it can
+//! be recorded next to the profile, e.g. as ad hoc events or tracing spans.
+//!
+//! Only available with the `profile` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+//!
+//! use thin_cell::{
+//!     profile::{self, Allocation, Profiler},
+//!     unsync::ThinCell,
+//! };
+//!
+//! struct Counter(AtomicUsize);
+//!
+//! impl Profiler for Counter {
+//!     fn allocated(&self, allocation: &Allocation) {
+//!         self.0.fetch_add(allocation.size, Relaxed);
+//!     }
+//!
+//!     fn freed(&self, _addr: usize, size: usize) {
+//!         self.0.fetch_sub(size, Relaxed);
+//!     }
+//! }
+//!
+//! static COUNTER: Counter = Counter(AtomicUsize::new(0));
+//! assert!(profile::set_profiler(&COUNTER).is_ok());
+//!
+//! let cell = ThinCell::new([0u8; 64]);
+//! assert_eq!(COUNTER.0.load(Relaxed), cell.allocated_size());
+//! ```
+
+use std::{panic::Location, sync::OnceLock};
+
+static PROFILER: OnceLock<&'static dyn Profiler> = OnceLock::new();
+
+/// An allocation made by `ThinCell`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Allocation {
+    /// Address of the allocation, as returned by `addr`.
+    pub addr: usize,
+    /// Size of the value and its header, as returned by `allocated_size`.
+    pub size: usize,
+    /// Type of the value at construction, before any unsizing.
+    pub type_name: &'static str,
+    /// Where the cell was constructed.
+    pub location: &'static Location<'static>,
+}
+
+/// Receives the allocations made by `ThinCell`s, see [`set_profiler`].
+pub trait Profiler: Sync {
+    /// Called when a cell has been allocated.
+    fn allocated(&self, allocation: &Allocation);
+
+    /// Called right before the allocation at `addr` of `size` bytes, as
+    /// reported by [`allocated`](Profiler::allocated), is freed.
+    fn freed(&self, addr: usize, size: usize);
+}
+
+/// Installs the profiler for the whole process.
+///
+/// Like `log::set_logger`, this can only be done once, and returns the given
+/// profiler back if one is installed already.
+pub fn set_profiler(profiler: &'static dyn Profiler) -> Result<(), &'static dyn Profiler> {
+    PROFILER.set(profiler)
+}
+
+pub(crate) fn allocated(allocation: Allocation) {
+    if let Some(profiler) = PROFILER.get() {
+        profiler.allocated(&allocation);
+    }
+}
+
+pub(crate) fn freed(addr: usize, size: usize) {
+    if let Some(profiler) = PROFILER.get() {
+        profiler.freed(addr, size);
+    }
+}
@@ -0,0 +1,188 @@
+/// Generates a trait with typed field projections for a `ThinCell` of a
+/// struct.
+///
+/// Each projection borrows the cell and returns a guard of just that field,
+/// built on `Ref::map`, so that nested access like `state.borrow().a.b` is
+/// written once and checked by the compiler.
+///
+/// The cell type is written as `unsync::ThinCell<T>` or `sync::ThinCell<T>`,
+/// and each projection as `fn name -> FieldType = path.to.field;`.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{thin_project, unsync::ThinCell};
+///
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// struct Config {
+///     name: String,
+///     server: Server,
+/// }
+///
+/// thin_project! {
+///     /// Field access for a shared `Config`.
+///     pub trait ConfigFields for unsync::ThinCell<Config> {
+///         fn name -> String = name;
+///         /// The port of the server.
+///         fn port -> u16 = server.port;
+///     }
+/// }
+///
+/// let config = ThinCell::new(Config {
+///     name: "app".into(),
+///     server: Server {
+///         host: "localhost".into(),
+///         port: 80,
+///     },
+/// });
+///
+/// *config.port() = 8080;
+/// config.name().push_str("-dev");
+///
+/// let config = config.borrow();
+/// assert_eq!(config.server.port, 8080);
+/// assert_eq!(config.name, "app-dev");
+/// ```
+#[macro_export]
+macro_rules! thin_project {
+    {
+        $( #[$meta:meta] )*
+        $vis:vis trait $name:ident for $flavour:ident::ThinCell<$ty:ty> {
+            $(
+                $( #[$method_meta:meta] )*
+                fn $method:ident -> $field_ty:ty = $( $field:tt ).+ ;
+            )*
+        }
+    } => {
+        $( #[$meta] )*
+        $vis trait $name {
+            $(
+                $( #[$method_meta] )*
+                ///
+                /// # Panics
+                ///
+                /// Panics (or blocks for the `sync` flavour) if the cell is already
+                /// borrowed.
+                fn $method(&self) -> $crate::$flavour::Ref<'_, $field_ty>;
+            )*
+        }
+
+        impl $name for $crate::$flavour::ThinCell<$ty> {
+            $(
+                #[track_caller]
+                fn $method(&self) -> $crate::$flavour::Ref<'_, $field_ty> {
+                    $crate::$flavour::Ref::map(self.borrow(), |value| &mut value.$( $field ).+)
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! projected_cell {
+    () => {
+        /// An owning handle to a part of the value of a [`ThinCell`], e.g. a field.
+        ///
+        /// It keeps the cell alive like any other handle, but only gives access to
+        /// the projected part, so a subsystem can be handed a single field of a
+        /// shared struct without seeing the rest. Created by [`ThinCell::project`].
+        ///
+        /// The whole cell is borrowed while the projected part is, so borrowing
+        /// conflicts with any other borrow of the same cell.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinCell;
+        /// struct App {
+        ///     title: String,
+        ///     clicks: u32,
+        /// }
+        ///
+        /// let app = ThinCell::new(App {
+        ///     title: "app".into(),
+        ///     clicks: 0,
+        /// });
+        ///
+        /// let clicks = app.clone().project(|app| &mut app.clicks);
+        /// *clicks.borrow() += 1;
+        ///
+        /// assert_eq!(app.borrow().clicks, 1);
+        /// ```
+        pub struct ProjectedCell<T: ?Sized, U: ?Sized> {
+            cell: ThinCell<T>,
+            project: fn(&mut T) -> &mut U,
+        }
+
+        impl<T: ?Sized> ThinCell<T> {
+            /// Converts this handle into one giving access to the part of the value
+            /// returned by `project` only.
+            ///
+            /// See [`ProjectedCell`].
+            pub fn project<U: ?Sized>(self, project: fn(&mut T) -> &mut U) -> ProjectedCell<T, U> {
+                ProjectedCell {
+                    cell: self,
+                    project,
+                }
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> ProjectedCell<T, U> {
+            /// Borrows the projected part mutably.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn borrow(&self) -> Ref<'_, U> {
+                Ref::map(self.cell.borrow(), self.project)
+            }
+
+            /// Tries to borrow the projected part mutably, returning `None` if the
+            /// cell is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow(&self) -> Option<Ref<'_, U>> {
+                self.cell
+                    .try_borrow()
+                    .map(|value| Ref::map(value, self.project))
+            }
+
+            /// Returns the number of owners of the cell.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if both handles project the same cell with the same
+            /// function.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell) && std::ptr::fn_addr_eq(self.project, other.project)
+            }
+        }
+
+        impl<T: ?Sized, U: ?Sized> Clone for ProjectedCell<T, U> {
+            fn clone(&self) -> Self {
+                ProjectedCell {
+                    cell: self.cell.clone(),
+                    project: self.project,
+                }
+            }
+        }
+
+        impl<T: ?Sized, U: Debug + ?Sized> Debug for ProjectedCell<T, U> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut d = f.debug_struct("ProjectedCell");
+                match self.try_borrow() {
+                    Some(borrowed) => d.field("value", &borrowed),
+                    None => d.field("value", &format_args!("<borrowed>")),
+                }
+                .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use projected_cell;
@@ -0,0 +1,223 @@
+//! Low-level access to the representation of `ThinCell`.
+//!
+//! This is meant for crates building their own cell-like types (task handles,
+//! slab entries, ...) on the same one-word pointer, without forking this
+//! crate. Most items here are `unsafe` and require understanding the layout
+//! below.
+//!
+//! # Layout
+//!
+//! A `ThinCell<T>` is a pointer to a `repr(C)` allocation shared by both
+//! flavours:
+//!
+//! ```text
+//! metadata: usize // pointer metadata of an unsized `T` (vtable, length), or 0
+//! state:    usize // reference count and flags, see below
+//! data:     T
+//! ```
+//!
+//! The pointer returned by `ThinCell::as_ptr` and `ThinCell::leak` points to
+//! `metadata`, at offset 0. The state word holds the number of owners in
+//! units of [`RC_UNIT`] within [`RC_MASK`], the borrow flag in
+//! [`BORROW_MASK`], and the [`EXTERNAL_MASK`] flag of cells whose memory is
+//! not owned by a `Box`.
+//!
+//! The state is read and modified through the `raw` module of each flavour,
+//! e.g. [`unsync::raw`](crate::unsync::raw), as the flavours synchronize it
+//! differently.
+//!
+//! # Examples
+//!
+//! ```
+//! use thin_cell::{raw, unsync::ThinCell};
+//!
+//! let cell = ThinCell::new(5u64);
+//! let ptr = cell.leak();
+//!
+//! // SAFETY: `ptr` is a live `unsync` cell holding a `u64`
+//! unsafe {
+//!     thin_cell::unsync::raw::increment(ptr);
+//!     assert_eq!(thin_cell::unsync::raw::load(ptr).count(), 2);
+//!     assert_eq!(*ptr.byte_add(raw::data_offset::<u64>()).cast::<u64>(), 5);
+//!
+//!     drop(ThinCell::<u64>::from_raw(ptr));
+//!     drop(ThinCell::<u64>::from_raw(ptr));
+//! }
+//! ```
+
+use std::alloc::Layout;
+
+pub use crate::state::{BORROW_MASK, EXTERNAL_MASK, MAX_COUNT, RC_MASK, RC_UNIT, Snapshot};
+
+/// Offset of the state word from the cell pointer.
+pub const STATE_OFFSET: usize = size_of::<usize>();
+
+/// Mask of the low pointer bits that are always zero for a cell, and can hold
+/// the tag of [`ThinCell::leak_tagged`](crate::unsync::ThinCell::leak_tagged).
+pub const TAG_MASK: usize = align_of::<usize>() - 1;
+
+/// Returns `true` if a pointer to `T` is fat, i.e. if a `ThinCell<T>` stores
+/// the metadata of `T` (a vtable or length) in its header.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::raw::is_fat;
+///
+/// assert!(!is_fat::<u64>());
+/// assert!(is_fat::<str>());
+/// assert!(is_fat::<dyn std::fmt::Debug>());
+/// ```
+pub const fn is_fat<T: ?Sized>() -> bool {
+    !crate::fat_ptr::is_sized::<T>()
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl<T: ?Sized> Sealed for T {}
+}
+
+/// Implemented by every type, to branch on [`is_fat`] through a constant in
+/// generic code.
+///
+/// This trait is sealed and can't be implemented outside of this crate.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::raw::MaybeThin;
+///
+/// fn kind<T: ?Sized>() -> &'static str {
+///     if <T as MaybeThin>::IS_FAT {
+///         "fat"
+///     } else {
+///         "thin"
+///     }
+/// }
+///
+/// assert_eq!(kind::<u8>(), "thin");
+/// assert_eq!(kind::<[u8]>(), "fat");
+/// ```
+pub trait MaybeThin: sealed::Sealed {
+    /// Whether a pointer to `Self` is fat, see [`is_fat`].
+    const IS_FAT: bool;
+}
+
+impl<T: ?Sized> MaybeThin for T {
+    const IS_FAT: bool = is_fat::<T>();
+}
+
+/// Returns the offset of the value from the cell pointer of a `ThinCell<T>`.
+pub const fn data_offset<T>() -> usize {
+    std::mem::offset_of!(crate::unsync::Inner<T>, data)
+}
+
+/// Returns the layout of the allocation of a `ThinCell<T>`.
+pub const fn layout<T>() -> Layout {
+    Layout::new::<crate::unsync::Inner<T>>()
+}
+
+macro_rules! thin_raw {
+    () => {
+        /// Low-level access to the state of cells of this flavour.
+        ///
+        /// See the crate level [`raw`](crate::raw) module for the layout. All
+        /// functions taking a `ptr` require it to point to a live cell of this
+        /// flavour, as returned by [`ThinCell::as_ptr`] or [`ThinCell::leak`].
+        pub mod raw {
+            use std::{cell::UnsafeCell, ptr::NonNull};
+
+            use super::{Inner, State, ThinCell};
+            use crate::raw::{STATE_OFFSET, Snapshot, data_offset};
+
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell.
+            unsafe fn state<'a>(ptr: *const ()) -> &'a State {
+                // SAFETY: `Inner` is `repr(C)` with `state` right after `metadata`
+                unsafe { &*ptr.byte_add(STATE_OFFSET).cast::<State>() }
+            }
+
+            /// Reads the current state of the cell.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell.
+            pub unsafe fn load(ptr: *const ()) -> Snapshot {
+                unsafe { state(ptr) }.load()
+            }
+
+            /// Adds an owner to the cell, like cloning a handle.
+            ///
+            /// The owner is given up by dropping a handle restored with
+            /// [`ThinCell::from_raw`].
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell.
+            pub unsafe fn increment(ptr: *const ()) {
+                unsafe { state(ptr) }.inc();
+            }
+
+            /// Tries to set the borrow flag of the cell, returning `false` if it is
+            /// already set.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell.
+            pub unsafe fn try_borrow(ptr: *const ()) -> bool {
+                unsafe { state(ptr) }.try_borrow()
+            }
+
+            /// Clears the borrow flag of the cell.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell, whose borrow flag was set by the
+            /// caller through [`try_borrow`], and no reference to the value obtained
+            /// under that borrow may be used afterwards.
+            pub unsafe fn unborrow(ptr: *const ()) {
+                unsafe { state(ptr) }.unborrow();
+            }
+
+            /// Returns a pointer to the value of a cell holding a `T`.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must point to a live cell holding a `T`.
+            pub unsafe fn data<T>(ptr: *const ()) -> *mut T {
+                unsafe { ptr.byte_add(data_offset::<T>()) }
+                    .cast_mut()
+                    .cast()
+            }
+
+            /// Creates a cell holding `value` in memory allocated by the caller.
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must have been allocated by the global allocator with
+            /// [`layout::<T>()`](crate::raw::layout), and must not be used other
+            /// than through the returned cell, which frees it with that layout once
+            /// the last owner is dropped.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub unsafe fn init<T>(ptr: NonNull<()>, value: T) -> ThinCell<T> {
+                // SAFETY: `ptr` is valid for writes of `Inner<T>`, guaranteed by caller
+                unsafe {
+                    ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new(),
+                        data: UnsafeCell::new(value),
+                    })
+                };
+
+                // SAFETY: `ptr` now holds a cell owned by the returned handle
+                let this = unsafe { ThinCell::from_raw(ptr.as_ptr()) };
+                this.register();
+                this
+            }
+        }
+    };
+}
+
+pub(crate) use thin_raw;
@@ -0,0 +1,146 @@
+macro_rules! thin_registry {
+    ($($bound:tt)*) => {
+        /// Keeps track of handles leaked into raw pointers, so the ones that are
+        /// never restored can be reclaimed in bulk.
+        ///
+        /// This suits drivers passing leaked cells to the kernel as user data of
+        /// I/O operations: on shutdown, some completions will never arrive, and
+        /// [`reclaim_all`](LeakRegistry::reclaim_all) drops the cells they still
+        /// own. Handles leaked with [`ThinCell::leak`] directly are not tracked.
+        ///
+        /// Dropping the registry does not reclaim anything, the remaining handles
+        /// stay leaked.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::{LeakRegistry, ThinCell};
+        /// let registry = LeakRegistry::new();
+        /// let cell = ThinCell::new(vec![0u8; 64]);
+        ///
+        /// let completed = registry.leak(cell.clone());
+        /// let _pending = registry.leak(cell.clone());
+        /// assert_eq!(cell.count(), 3);
+        ///
+        /// // SAFETY: `completed` was leaked from a `ThinCell<Vec<u8>>`
+        /// let restored = unsafe { registry.from_raw::<Vec<u8>>(completed) };
+        /// assert!(restored.is_some());
+        /// drop(restored);
+        ///
+        /// // On shutdown
+        /// assert_eq!(registry.reclaim_all(), 1);
+        /// assert_eq!(cell.count(), 1);
+        /// ```
+        pub struct LeakRegistry {
+            leaked: Mutex<HashMap<usize, Leaked>>,
+        }
+
+        /// Handles of a cell leaked into a [`LeakRegistry`], which all share the
+        /// same pointer.
+        struct Leaked {
+            ptr: *mut (),
+            count: usize,
+            drop: unsafe fn(*mut ()),
+        }
+
+        impl LeakRegistry {
+            /// Creates an empty registry.
+            pub fn new() -> Self {
+                LeakRegistry {
+                    leaked: Mutex::new(HashMap::new()),
+                }
+            }
+
+            /// Leaks `cell` like [`ThinCell::leak`], recording the pointer so it
+            /// can be reclaimed by [`reclaim_all`](LeakRegistry::reclaim_all).
+            pub fn leak<T: ?Sized + 'static $($bound)*>(&self, cell: ThinCell<T>) -> *mut () {
+                /// Restores and drops a handle leaked by `leak`.
+                unsafe fn drop_leaked<T: ?Sized>(ptr: *mut ()) {
+                    // SAFETY: `ptr` was leaked from a `ThinCell<T>`
+                    drop(unsafe { ThinCell::<T>::from_raw(ptr) });
+                }
+
+                let ptr = cell.leak();
+                self.leaked
+                    .lock()
+                    .entry(ptr.addr())
+                    .or_insert(Leaked {
+                        ptr,
+                        count: 0,
+                        drop: drop_leaked::<T>,
+                    })
+                    .count += 1;
+                ptr
+            }
+
+            /// Restores a handle leaked by [`leak`](LeakRegistry::leak) and stops
+            /// tracking it, or returns `None` if it is not tracked anymore, e.g.
+            /// because it was reclaimed by [`reclaim_all`](LeakRegistry::reclaim_all).
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must have been returned by [`leak`](LeakRegistry::leak) on
+            /// this registry for a `ThinCell<T>`. If it may have been reclaimed, no
+            /// other cell may have been leaked into this registry since, as it
+            /// could reuse the same address.
+            pub unsafe fn from_raw<T: ?Sized>(&self, ptr: *mut ()) -> Option<ThinCell<T>> {
+                let mut leaked = self.leaked.lock();
+                let entry = leaked.get_mut(&ptr.addr())?;
+                entry.count -= 1;
+                if entry.count == 0 {
+                    leaked.remove(&ptr.addr());
+                }
+                drop(leaked);
+
+                // SAFETY: guaranteed by caller, and the handle is not tracked anymore
+                Some(unsafe { ThinCell::from_raw(ptr) })
+            }
+
+            /// Drops every handle leaked into this registry and not restored yet,
+            /// returning how many were dropped.
+            ///
+            /// The handles are dropped after the registry is released, so their
+            /// values may use it when dropped.
+            pub fn reclaim_all(&self) -> usize {
+                let leaked = std::mem::take(&mut *self.leaked.lock());
+                let mut dropped = 0;
+                for Leaked { ptr, count, drop: drop_leaked } in leaked.into_values() {
+                    for _ in 0..count {
+                        // SAFETY: `drop_leaked` matches the type of the leaked handles,
+                        // each of which is dropped once.
+                        unsafe { drop_leaked(ptr) };
+                    }
+                    dropped += count;
+                }
+                dropped
+            }
+
+            /// Returns the number of handles leaked into this registry and not
+            /// restored or reclaimed yet.
+            pub fn len(&self) -> usize {
+                self.leaked.lock().values().map(|leaked| leaked.count).sum()
+            }
+
+            /// Returns `true` if no handle is tracked by this registry.
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+        }
+
+        impl Default for LeakRegistry {
+            fn default() -> Self {
+                LeakRegistry::new()
+            }
+        }
+
+        impl Debug for LeakRegistry {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("LeakRegistry")
+                    .field("len", &self.len())
+                    .finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_registry;
@@ -0,0 +1,254 @@
+macro_rules! thin_serde_registry {
+    ($($bound:tt)+) => {
+        /// Type of the values of the cells, named to be borrowed without
+        /// parentheses around a single bound.
+        type SerdeValue = dyn $($bound)+;
+
+        /// How to serialize and deserialize a type registered in a
+        /// [`SerdeRegistry`].
+        struct SerdeEntry {
+            name: &'static str,
+            serialize: fn(&SerdeValue) -> &dyn erased_serde::Serialize,
+            deserialize: fn(
+                &mut dyn erased_serde::Deserializer<'_>,
+            ) -> Result<ThinCell<dyn $($bound)+>, erased_serde::Error>,
+        }
+
+        /// A set of types that type-erased cells can be serialized as, each under
+        /// a stable name.
+        ///
+        /// A cell is serialized as a `type` and `value` pair, e.g.
+        /// `{"type":"name","value":"Ferris"}` in JSON, which is deserialized back
+        /// into a new cell by looking up the name. Serializing a cell whose value
+        /// is not registered, or is currently borrowed, fails.
+        ///
+        /// Only available with the `erased-serde` feature.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::{SerdeRegistry, ThinCell};
+        /// use std::any::Any;
+        ///
+        /// let mut registry = SerdeRegistry::new();
+        /// registry.register::<u32>("count").register::<String>("name");
+        ///
+        /// let cell = unsafe { ThinCell::<dyn Any>::new_unsize(7u32, |p| p as _) };
+        /// let json = serde_json::to_string(&registry.tagged(&cell)).unwrap();
+        /// assert_eq!(json, r#"{"type":"count","value":7}"#);
+        ///
+        /// let mut de = serde_json::Deserializer::from_str(&json);
+        /// let cell = registry.deserialize(&mut de).unwrap();
+        /// assert_eq!(*cell.downcast::<u32>().ok().unwrap().borrow(), 7);
+        /// ```
+        pub struct SerdeRegistry {
+            by_type: HashMap<TypeId, SerdeEntry>,
+            by_name: HashMap<&'static str, TypeId>,
+        }
+
+        impl SerdeRegistry {
+            /// Creates an empty registry.
+            pub fn new() -> Self {
+                SerdeRegistry {
+                    by_type: HashMap::new(),
+                    by_name: HashMap::new(),
+                }
+            }
+
+            /// Registers `T` under `name`, replacing the name it was registered
+            /// under before, if any.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `name` is already used by another type.
+            pub fn register<T>(&mut self, name: &'static str) -> &mut Self
+            where
+                T: $($bound)+ + serde::Serialize + serde::de::DeserializeOwned,
+            {
+                fn serialize<T: $($bound)+ + serde::Serialize>(
+                    value: &SerdeValue,
+                ) -> &dyn erased_serde::Serialize {
+                    // Entries are keyed by the `TypeId` of their type
+                    value.downcast_ref::<T>().unwrap()
+                }
+
+                fn deserialize<T: $($bound)+ + serde::de::DeserializeOwned>(
+                    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+                ) -> Result<ThinCell<dyn $($bound)+>, erased_serde::Error> {
+                    let value = erased_serde::deserialize::<T>(deserializer)?;
+                    // SAFETY: unsized coercion from `T` to `dyn Any` is safe
+                    Ok(unsafe { ThinCell::new_unsize(value, |ptr| ptr as _) })
+                }
+
+                let type_id = TypeId::of::<T>();
+                match self.by_name.get(name) {
+                    Some(&other) if other != type_id => {
+                        panic!("Type name `{name}` is already registered")
+                    }
+                    _ => {}
+                }
+
+                let entry = SerdeEntry {
+                    name,
+                    serialize: serialize::<T>,
+                    deserialize: deserialize::<T>,
+                };
+                if let Some(old) = self.by_type.insert(type_id, entry) {
+                    self.by_name.remove(old.name);
+                }
+                self.by_name.insert(name, type_id);
+                self
+            }
+
+            /// Returns `true` if `T` is registered.
+            pub fn contains<T: $($bound)+>(&self) -> bool {
+                self.by_type.contains_key(&TypeId::of::<T>())
+            }
+
+            /// Wraps `cell` to serialize it along with the name of its type.
+            pub fn tagged<'a>(&'a self, cell: &'a ThinCell<dyn $($bound)+>) -> Tagged<'a> {
+                Tagged {
+                    registry: self,
+                    cell,
+                }
+            }
+
+            /// Deserializes a cell serialized through [`tagged`](Self::tagged).
+            pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+                &self,
+                deserializer: D,
+            ) -> Result<ThinCell<dyn $($bound)+>, D::Error> {
+                serde::de::DeserializeSeed::deserialize(self, deserializer)
+            }
+        }
+
+        impl Default for SerdeRegistry {
+            fn default() -> Self {
+                SerdeRegistry::new()
+            }
+        }
+
+        impl Debug for SerdeRegistry {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_set().entries(self.by_name.keys()).finish()
+            }
+        }
+
+        /// A type-erased cell serialized along with the name of its type, returned
+        /// by [`SerdeRegistry::tagged`].
+        pub struct Tagged<'a> {
+            registry: &'a SerdeRegistry,
+            cell: &'a ThinCell<dyn $($bound)+>,
+        }
+
+        impl serde::Serialize for Tagged<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::{Error, SerializeStruct};
+
+                let value = self
+                    .cell
+                    .try_borrow()
+                    .ok_or_else(|| S::Error::custom("cell is already borrowed"))?;
+                let entry = self
+                    .registry
+                    .by_type
+                    .get(&(*value).type_id())
+                    .ok_or_else(|| S::Error::custom("type of the cell is not registered"))?;
+
+                let mut tagged = serializer.serialize_struct("ThinCell", 2)?;
+                tagged.serialize_field("type", entry.name)?;
+                tagged.serialize_field("value", (entry.serialize)(&*value))?;
+                tagged.end()
+            }
+        }
+
+        impl Debug for Tagged<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("Tagged").finish_non_exhaustive()
+            }
+        }
+
+        impl<'de> serde::de::DeserializeSeed<'de> for &SerdeRegistry {
+            type Value = ThinCell<dyn $($bound)+>;
+
+            fn deserialize<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                /// Deserializes the value of a type looked up by name.
+                struct ValueSeed<'a>(&'a SerdeEntry);
+
+                impl<'de> serde::de::DeserializeSeed<'de> for ValueSeed<'_> {
+                    type Value = ThinCell<dyn $($bound)+>;
+
+                    fn deserialize<D: serde::Deserializer<'de>>(
+                        self,
+                        deserializer: D,
+                    ) -> Result<Self::Value, D::Error> {
+                        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+                        (self.0.deserialize)(&mut erased).map_err(serde::de::Error::custom)
+                    }
+                }
+
+                struct TaggedVisitor<'a>(&'a SerdeRegistry);
+
+                impl TaggedVisitor<'_> {
+                    fn entry<E: serde::de::Error>(&self, name: &str) -> Result<&SerdeEntry, E> {
+                        self.0
+                            .by_name
+                            .get(name)
+                            .and_then(|type_id| self.0.by_type.get(type_id))
+                            .ok_or_else(|| E::custom(format_args!("unknown type `{name}`")))
+                    }
+                }
+
+                impl<'de> serde::de::Visitor<'de> for TaggedVisitor<'_> {
+                    type Value = ThinCell<dyn $($bound)+>;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str("a `type` and `value` pair")
+                    }
+
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                        self,
+                        mut seq: A,
+                    ) -> Result<Self::Value, A::Error> {
+                        use serde::de::Error;
+
+                        let name = seq
+                            .next_element::<String>()?
+                            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                        let entry = self.entry(&name)?;
+                        seq.next_element_seed(ValueSeed(entry))?
+                            .ok_or_else(|| A::Error::invalid_length(1, &self))
+                    }
+
+                    fn visit_map<A: serde::de::MapAccess<'de>>(
+                        self,
+                        mut map: A,
+                    ) -> Result<Self::Value, A::Error> {
+                        use serde::de::Error;
+
+                        // The type comes first, as it is needed to read the value
+                        match map.next_key::<String>()?.as_deref() {
+                            Some("type") => {}
+                            Some(_) => return Err(A::Error::custom("expected `type` first")),
+                            None => return Err(A::Error::missing_field("type")),
+                        }
+                        let name = map.next_value::<String>()?;
+                        let entry = self.entry(&name)?;
+                        match map.next_key::<String>()?.as_deref() {
+                            Some("value") => map.next_value_seed(ValueSeed(entry)),
+                            Some(_) => Err(A::Error::custom("expected `value` after `type`")),
+                            None => Err(A::Error::missing_field("value")),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_struct("ThinCell", &["type", "value"], TaggedVisitor(self))
+            }
+        }
+    };
+}
+
+pub(crate) use thin_serde_registry;
@@ -0,0 +1,268 @@
+macro_rules! thin_slab {
+    () => {
+        /// Number of slots allocated at once when a [`ThinSlab`] grows.
+        const PAGE_SLOTS: usize = 32;
+
+        /// Key of a value in a [`ThinSlab`].
+        ///
+        /// Keys are generational: once a value is removed, its key never refers to
+        /// a value inserted later into the same slot.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct SlabKey {
+            index: u32,
+            generation: u32,
+        }
+
+        /// A slab storing `ThinCell`s densely in pages of slots.
+        ///
+        /// Inserting a value hands out a [`SlabKey`]; the slab keeps one handle of
+        /// the cell, which can be cloned out with [`ThinSlab::get`] to share it.
+        /// Removing a value only releases the slab's handle, and the slot is reused
+        /// once every other handle of that cell has been dropped.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinSlab;
+        /// let mut slab = ThinSlab::new();
+        ///
+        /// let key = slab.insert(42);
+        /// let cell = slab.get(key).unwrap().clone();
+        /// *cell.borrow() += 1;
+        ///
+        /// let removed = slab.remove(key).unwrap();
+        /// assert!(removed.ptr_eq(&cell));
+        /// assert!(slab.get(key).is_none());
+        /// ```
+        pub struct ThinSlab<T> {
+            shared: Shared<SlabShared<T>>,
+            entries: Vec<SlabEntry<T>>,
+            len: usize,
+        }
+
+        struct SlabEntry<T> {
+            generation: u32,
+            cell: Option<ThinCell<T>>,
+        }
+
+        /// State shared between the slab and the cells living in its pages.
+        ///
+        /// Each live cell owns a strong reference to it, so pages are only freed
+        /// when both the slab and all of its cells are gone.
+        struct SlabShared<T> {
+            slots: Mutex<SlabSlots<T>>,
+        }
+
+        struct SlabSlots<T> {
+            pages: Vec<NonNull<SlabPage<T>>>,
+            /// Indices of the slots whose memory can be reused.
+            free: Vec<u32>,
+        }
+
+        /// Header at the start of each page allocation.
+        #[repr(C)]
+        struct SlabPage<T> {
+            header: Release,
+            shared: *const SlabShared<T>,
+            /// Index of the first slot in this page.
+            base: u32,
+        }
+
+        impl<T> SlabPage<T> {
+            fn layout() -> SlotsLayout {
+                SlotsLayout::new::<SlabPage<T>, Inner<T>>(PAGE_SLOTS)
+            }
+
+            /// # Safety
+            ///
+            /// `page` must be a live page, and `i < PAGE_SLOTS`.
+            unsafe fn slot(page: NonNull<SlabPage<T>>, i: usize) -> NonNull<()> {
+                let SlotsLayout { first, stride, .. } = Self::layout();
+                unsafe { page.cast::<u8>().add(first + i * stride).cast() }
+            }
+
+            unsafe fn release(header: NonNull<Release>, inner: NonNull<()>) {
+                let page = header.cast::<SlabPage<T>>();
+                let SlotsLayout { first, stride, .. } = Self::layout();
+
+                // SAFETY: `inner` is one of the slots of `page`
+                let offset = unsafe { inner.cast::<u8>().offset_from(page.cast::<u8>()) };
+                let index = (offset as usize - first) / stride;
+
+                // SAFETY: The page is alive as long as `SlabShared` is, and the cell
+                // being released owned one strong reference to it.
+                let shared = unsafe {
+                    let page = page.as_ref();
+                    Shared::from_raw(page.shared)
+                };
+                shared
+                    .slots
+                    .lock()
+                    .free
+                    .push(unsafe { page.as_ref() }.base + index as u32);
+            }
+        }
+
+        impl<T> Drop for SlabShared<T> {
+            fn drop(&mut self) {
+                let layout = SlabPage::<T>::layout().layout;
+                for page in self.slots.lock().pages.drain(..) {
+                    // SAFETY: All cells are gone as each of them keeps `self` alive
+                    unsafe { dealloc(page.as_ptr().cast(), layout) }
+                }
+            }
+        }
+
+        impl<T> ThinSlab<T> {
+            /// Creates an empty slab. No memory is allocated until the first insert.
+            pub fn new() -> Self {
+                ThinSlab {
+                    shared: Shared::new(SlabShared {
+                        slots: Mutex::new(SlabSlots {
+                            pages: Vec::new(),
+                            free: Vec::new(),
+                        }),
+                    }),
+                    entries: Vec::new(),
+                    len: 0,
+                }
+            }
+
+            /// Returns the number of values in the slab.
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// Returns `true` if the slab contains no values.
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// Inserts a value into a new cell, returning its key.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn insert(&mut self, data: T) -> SlabKey {
+                let (index, ptr) = {
+                    let mut slots = self.shared.slots.lock();
+                    if slots.free.is_empty() {
+                        self.grow(&mut slots);
+                    }
+                    let index = slots.free.pop().unwrap();
+                    let page = slots.pages[index as usize / PAGE_SLOTS];
+
+                    // SAFETY: `page` is live and the offset is within it
+                    (index, unsafe {
+                        SlabPage::slot(page, index as usize % PAGE_SLOTS)
+                    })
+                };
+
+                // SAFETY: The slot is free, and the new cell owns a strong reference
+                // to the shared state as required by `SlabPage::release`.
+                let cell = unsafe {
+                    ptr.cast::<Inner<T>>().write(Inner {
+                        metadata: 0,
+                        state: State::new_external(),
+                        data: UnsafeCell::new(data),
+                    });
+                    Shared::increment_strong_count(Shared::as_ptr(&self.shared));
+
+                    ThinCell {
+                        ptr,
+                        _marker: PhantomData,
+                    }
+                };
+                cell.register();
+
+                let index = index as usize;
+                if index >= self.entries.len() {
+                    self.entries.resize_with(index + 1, || SlabEntry {
+                        generation: 0,
+                        cell: None,
+                    });
+                }
+                let entry = &mut self.entries[index];
+                entry.cell = Some(cell);
+                self.len += 1;
+
+                SlabKey {
+                    index: index as u32,
+                    generation: entry.generation,
+                }
+            }
+
+            /// Returns the cell of `key`, or `None` if it has been removed.
+            pub fn get(&self, key: SlabKey) -> Option<&ThinCell<T>> {
+                self.entries
+                    .get(key.index as usize)
+                    .filter(|entry| entry.generation == key.generation)
+                    .and_then(|entry| entry.cell.as_ref())
+            }
+
+            /// Returns `true` if `key` refers to a value in the slab.
+            pub fn contains(&self, key: SlabKey) -> bool {
+                self.get(key).is_some()
+            }
+
+            /// Removes the cell of `key` from the slab, returning the slab's handle.
+            ///
+            /// The slot is reused once all handles of the cell are dropped.
+            pub fn remove(&mut self, key: SlabKey) -> Option<ThinCell<T>> {
+                let entry = self
+                    .entries
+                    .get_mut(key.index as usize)
+                    .filter(|entry| entry.generation == key.generation)?;
+                let cell = entry.cell.take()?;
+
+                entry.generation = entry.generation.wrapping_add(1);
+                self.len -= 1;
+
+                Some(cell)
+            }
+
+            /// Allocates a new page and adds its slots to the free list.
+            fn grow(&self, slots: &mut SlabSlots<T>) {
+                let layout = SlabPage::<T>::layout().layout;
+                let end = u32::try_from((slots.pages.len() + 1) * PAGE_SLOTS)
+                    .expect("Too many slots in `ThinSlab`");
+                let base = end - PAGE_SLOTS as u32;
+
+                // SAFETY: `layout` has non-zero size
+                let Some(page) = NonNull::new(unsafe { alloc(layout) }) else {
+                    handle_alloc_error(layout)
+                };
+                let page = page.cast::<SlabPage<T>>();
+
+                // SAFETY: `page` is a fresh allocation laid out by `SlabPage::layout`
+                unsafe {
+                    page.write(SlabPage {
+                        header: Release {
+                            release: SlabPage::<T>::release,
+                        },
+                        shared: Shared::as_ptr(&self.shared),
+                        base,
+                    });
+                    for i in 0..PAGE_SLOTS {
+                        set_header(SlabPage::slot(page, i), page.cast());
+                    }
+                }
+
+                slots.pages.push(page);
+                // Hand out lower slots first
+                slots.free.extend((base..end).rev());
+            }
+        }
+
+        impl<T> Default for ThinSlab<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T> Debug for ThinSlab<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("ThinSlab").field("len", &self.len).finish()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_slab;
@@ -0,0 +1,167 @@
+/// Resolves `range` against a slice of `len` elements.
+///
+/// # Panics
+///
+/// Panics if the range is out of bounds or decreasing, like slice indexing.
+#[track_caller]
+pub(crate) fn resolve(range: impl std::ops::RangeBounds<usize>, len: usize) -> (usize, usize) {
+    use std::ops::Bound;
+
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "slice index starts at {start} but ends at {end}"
+    );
+    assert!(
+        end <= len,
+        "range end index {end} out of range for slice of length {len}"
+    );
+    (start, end - start)
+}
+
+macro_rules! thin_slice_view {
+    () => {
+        /// An owning view of a range of a `ThinCell<[T]>`, like `bytes::Bytes`.
+        ///
+        /// It holds a handle of the cell with an offset and a length, so windows
+        /// over one shared buffer are created and sub-sliced without copying.
+        /// Borrowing the view borrows the whole cell.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinCell;
+        /// let buf = ThinCell::new(*b"GET /index HTTP/1.1").unsize_slice();
+        ///
+        /// let path = buf.slice(4..10);
+        /// assert_eq!(&*path.borrow(), b"/index");
+        ///
+        /// let name = path.slice(1..);
+        /// name.borrow().make_ascii_uppercase();
+        /// assert_eq!(&*buf.borrow(), b"GET /INDEX HTTP/1.1");
+        /// ```
+        pub struct ThinSlice<T> {
+            cell: ThinCell<[T]>,
+            start: usize,
+            len: usize,
+        }
+
+        impl<T> ThinCell<[T]> {
+            /// Returns an owning view of `range` of the slice, sharing this cell.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `range` is out of bounds.
+            #[track_caller]
+            pub fn slice(&self, range: impl std::ops::RangeBounds<usize>) -> ThinSlice<T> {
+                let (start, len) = crate::slice::resolve(range, self.inner().metadata);
+                ThinSlice {
+                    cell: self.clone(),
+                    start,
+                    len,
+                }
+            }
+        }
+
+        impl<T> ThinSlice<T> {
+            /// Returns a view of `range` of this view, sharing the same cell.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `range` is out of bounds of this view.
+            #[track_caller]
+            pub fn slice(&self, range: impl std::ops::RangeBounds<usize>) -> ThinSlice<T> {
+                let (start, len) = crate::slice::resolve(range, self.len);
+                ThinSlice {
+                    cell: self.cell.clone(),
+                    start: self.start + start,
+                    len,
+                }
+            }
+
+            /// Returns the number of elements in the view.
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// Returns `true` if the view has no elements.
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// Returns the offset of the view in the cell.
+            pub fn offset(&self) -> usize {
+                self.start
+            }
+
+            /// Returns the cell the view points into.
+            pub fn cell(&self) -> &ThinCell<[T]> {
+                &self.cell
+            }
+
+            /// Borrows the elements of the view mutably.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the cell is already
+            /// borrowed, see [`ThinCell::borrow`].
+            #[track_caller]
+            pub fn borrow(&self) -> Ref<'_, [T]> {
+                Ref::map(self.cell.borrow(), |slice| {
+                    &mut slice[self.start..self.start + self.len]
+                })
+            }
+
+            /// Tries to borrow the elements of the view mutably, returning `None` if
+            /// the cell is already borrowed.
+            #[cfg_attr(feature = "track-borrow", track_caller)]
+            pub fn try_borrow(&self) -> Option<Ref<'_, [T]>> {
+                let slice = self.cell.try_borrow()?;
+                Some(Ref::map(slice, |slice| {
+                    &mut slice[self.start..self.start + self.len]
+                }))
+            }
+        }
+
+        impl<T> Clone for ThinSlice<T> {
+            fn clone(&self) -> Self {
+                ThinSlice {
+                    cell: self.cell.clone(),
+                    start: self.start,
+                    len: self.len,
+                }
+            }
+        }
+
+        impl<T> From<ThinCell<[T]>> for ThinSlice<T> {
+            fn from(cell: ThinCell<[T]>) -> Self {
+                let len = cell.inner().metadata;
+                ThinSlice {
+                    cell,
+                    start: 0,
+                    len,
+                }
+            }
+        }
+
+        impl<T: Debug> Debug for ThinSlice<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.try_borrow() {
+                    Some(borrowed) => Debug::fmt(&*borrowed, f),
+                    None => write!(f, "<borrowed>"),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use thin_slice_view;
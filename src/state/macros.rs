@@ -7,13 +7,67 @@ macro_rules! impl_state {
 
         /// Encapsulates the bitwise logic for Reference Counting and borrow flags.
         ///
-        /// All bits except last are used for Reference Count (RC), while last bit is
-        /// used for borrow flags (Borrowed).
+        /// All bits except the last two are used for Reference Count (RC), while last
+        /// bit is used for borrow flags (Borrowed) and the second last one tells
+        /// whether the allocation is external (see [`EXTERNAL_MASK`]).
         ///
         $( #[$meta] )*
         #[repr(transparent)]
         pub struct State($inner);
 
+        #[cold]
+        fn borrowed_drop() {
+            if cfg!(feature = "abort-on-borrowed-drop") {
+                eprintln!("`ThinCell` dropped while borrowed, aborting");
+                abort()
+            }
+        }
+
+        #[cold]
+        #[track_caller]
+        fn used_after_free() -> ! {
+            eprintln!(
+                "`ThinCell` used after being freed at {}, aborting",
+                std::panic::Location::caller()
+            );
+            abort()
+        }
+
+        #[cfg(thin_cell_validate)]
+        #[cold]
+        #[track_caller]
+        fn invariant_violated(invariant: &str, state: Snapshot) -> ! {
+            eprintln!(
+                "`ThinCell` invariant violated at {}: {invariant} in {state:?}, aborting",
+                std::panic::Location::caller()
+            );
+            abort()
+        }
+
+        /// Checks the state `orig` an operation found on a live cell, when built
+        /// with `--cfg thin_cell_validate`: it has an owner and its count is in
+        /// range.
+        #[inline(always)]
+        #[track_caller]
+        fn validate(orig: usize) {
+            #[cfg(thin_cell_validate)]
+            {
+                let state = Snapshot(orig);
+                if state.count() == 0 {
+                    invariant_violated("no owner", state);
+                }
+                if state.count() > MAX_COUNT {
+                    invariant_violated("count out of range", state);
+                }
+                if orig == POISON {
+                    invariant_violated("freed", state);
+                }
+            }
+
+            #[cfg(not(thin_cell_validate))]
+            let _ = orig;
+        }
+
         impl std::fmt::Debug for State {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 f.debug_tuple("State").field(&self.load()).finish()
@@ -26,19 +80,51 @@ macro_rules! impl_state {
                 State($inner::new(RC_UNIT))
             }
 
+            /// Creates the state of a cell whose allocation is released through its
+            /// external header, see [`EXTERNAL_MASK`].
+            pub fn new_external() -> Self {
+                State($inner::new(RC_UNIT | EXTERNAL_MASK))
+            }
+
+            /// Creates a state with given reference count, used as a plain counter.
+            pub fn with_count(count: usize) -> Self {
+                debug_assert!(count <= MAX_COUNT);
+                State($inner::new(count * RC_UNIT))
+            }
+
             pub fn load(&self) -> Snapshot {
                 self.0.load(Relaxed).into()
             }
 
+            /// Like `load`, but synchronizes with the release of the last borrow.
+            pub fn load_acquire(&self) -> Snapshot {
+                self.0.load(Acquire).into()
+            }
+
             pub fn inc(&self) -> &Self {
+                if cfg!(feature = "saturating") {
+                    // Once saturated, the count sticks at the maximum and the allocation
+                    // is leaked instead of overflowing.
+                    let orig = self.0.fetch_update(Relaxed, Relaxed, |curr| {
+                        (Snapshot(curr).count() < MAX_COUNT).then(|| curr + RC_UNIT)
+                    });
+                    validate(orig.unwrap_or_else(|curr| curr));
+                    return self;
+                }
+
                 // As explained in `Arc`'s comment, use relaxed ordering is fine for
                 // reference count increment.
                 let orig = self.0.fetch_add(RC_UNIT, Relaxed);
+                validate(orig);
 
                 // Quote unquote from `Arc`:
                 // > This branch will never be taken in any realistic program. We abort because
                 // > such a program is incredibly degenerate, and we don't care to support it.
-                if (orig & RC_MASK) == RC_MASK {
+                //
+                // With `abort-on-overflow`, the count is checked long before it could
+                // wrap, so `Clone` never unwinds and no arithmetic overflow can happen
+                // either, even with racing increments.
+                if Snapshot(orig).count() >= MAX_COUNT {
                     abort()
                 }
 
@@ -48,17 +134,36 @@ macro_rules! impl_state {
             /// Decrease reference count by one.
             ///
             /// Returns whether reference count has reached zero (needs drop).
+            ///
+            /// If the last owner is gone while the value is still borrowed, e.g. when
+            /// a guard was passed to `mem::forget` before every handle was dropped,
+            /// the allocation is leaked instead of being freed from under the guard,
+            /// or the process is aborted with the `abort-on-borrowed-drop` feature.
             pub fn dec(&self) -> bool {
+                let orig = if cfg!(feature = "saturating") {
+                    // A saturated count never decreases, see `inc`.
+                    let orig = self.0.fetch_update(Release, Relaxed, |curr| {
+                        (Snapshot(curr).count() < MAX_COUNT).then(|| curr - RC_UNIT)
+                    });
+                    match orig {
+                        Ok(orig) => orig,
+                        Err(_) => return false,
+                    }
+                } else {
+                    self.0.fetch_sub(RC_UNIT, Release)
+                };
+                validate(orig);
+
                 // Because `fetch_sub` is already atomic, we do not need to synchronize
                 // with other threads unless we are going to delete the object.
-                if self.0.fetch_sub(RC_UNIT, Release) != RC_UNIT {
+                if orig & RC_MASK != RC_UNIT {
                     return false;
                 }
 
-                debug_assert!(
-                    !self.load().is_borrowed(),
-                    "Reference count should never reach zero while borrowed"
-                );
+                if orig & BORROW_MASK != 0 {
+                    borrowed_drop();
+                    return false;
+                }
 
                 // Prevent any other thread from reading after we have decremented the
                 // count to zero, which could lead to use-after-free.
@@ -66,6 +171,7 @@ macro_rules! impl_state {
 
                 true
             }
+
             /// Try to zero the reference count if there is only one owner and not borrowed.
             ///
             /// Returns whether the unwrapping is successful (i.e., we can safely take the underlying object).
@@ -73,9 +179,10 @@ macro_rules! impl_state {
                 // Only when `state == RC_UNIT` (one owner, not borrowed) can we safely unwrap.
                 // Any other state means either multiple owners or borrowed, both of which
                 // prevent unwrapping.
+                let flags = self.0.load(Relaxed) & EXTERNAL_MASK;
                 if self
                     .0
-                    .compare_exchange(RC_UNIT, 0, Release, Relaxed)
+                    .compare_exchange(RC_UNIT | flags, flags, Release, Relaxed)
                     .is_err()
                 {
                     return false;
@@ -88,9 +195,37 @@ macro_rules! impl_state {
                 true
             }
 
+            /// Gives a single owner back to a cell whose count was zeroed by
+            /// [`try_unwrap`](State::try_unwrap), keeping the external flag.
+            pub fn reset_unique(&self) {
+                let flags = self.0.load(Relaxed) & EXTERNAL_MASK;
+                self.0.store(RC_UNIT | flags, Relaxed);
+            }
+
             pub fn unborrow(&self) {
-                // Keep RC bits, clear Borrow bits
-                self.0.fetch_and(RC_MASK, Release);
+                // Keep RC and external bits, clear Borrow bits
+                let orig = self.0.fetch_and(!BORROW_MASK, Release);
+
+                // The last owner may be gone if it was dropped while borrowed, see `dec`
+                #[cfg(thin_cell_validate)]
+                if !Snapshot(orig).is_borrowed() {
+                    invariant_violated("not borrowed", Snapshot(orig));
+                }
+                #[cfg(not(thin_cell_validate))]
+                let _ = orig;
+            }
+
+            /// Overwrites the state of a cell being freed with [`POISON`].
+            pub fn poison(&self) {
+                self.0.store(POISON, Relaxed);
+            }
+
+            /// Aborts if the state is [`POISON`], i.e. the cell has been freed.
+            #[track_caller]
+            pub fn check_poison(&self) {
+                if self.0.load(Relaxed) == POISON {
+                    used_after_free();
+                }
             }
         }
     };
@@ -106,6 +241,49 @@ macro_rules! test_cases {
             assert!(!state.is_shared());
         }
 
+        #[test]
+        fn test_state_new_external() {
+            let state = State::new_external();
+            assert!(state.load().is_external());
+            assert_eq!(state.load().count(), 1);
+
+            state.inc();
+            state.borrow();
+            state.unborrow();
+            assert!(state.load().is_external());
+            assert!(!state.dec());
+            assert!(state.load().is_external());
+            assert!(state.dec());
+
+            let state = State::new_external();
+            assert!(state.try_unwrap());
+            assert!(state.load().is_external());
+            assert_eq!(state.load().count(), 0);
+
+            state.reset_unique();
+            assert!(state.load().is_external());
+            assert_eq!(state.load().count(), 1);
+        }
+
+        #[test]
+        fn test_state_poison() {
+            let state = State::new();
+            state.check_poison();
+
+            state.poison();
+            assert_eq!(state.load().0, POISON);
+        }
+
+        #[test]
+        fn test_state_with_count() {
+            let state = State::with_count(3);
+            assert_eq!(state.load().count(), 3);
+            assert!(!state.load().is_external());
+            assert!(!state.dec());
+            assert!(!state.dec());
+            assert!(state.dec());
+        }
+
         #[test]
         fn test_state_count() {
             let state = State::new();
@@ -146,6 +324,21 @@ macro_rules! test_cases {
             assert_eq!(state.load().count(), 1);
         }
 
+        #[test]
+        #[cfg(not(feature = "abort-on-borrowed-drop"))]
+        fn test_state_dec_while_borrowed() {
+            let state = State::new();
+            state.borrow();
+
+            // Last owner gone while borrowed: leaked, never reported as droppable
+            assert!(!state.dec());
+            assert_eq!(state.load().count(), 0);
+            assert!(state.load().is_borrowed());
+
+            state.unborrow();
+            assert_eq!(state.load().count(), 0);
+        }
+
         #[test]
         fn test_state_is_shared() {
             let state = State::new();
@@ -255,6 +448,26 @@ macro_rules! test_cases {
             state1.borrow();
             assert_ne!(state1.load(), state2.load());
         }
+
+        #[test]
+        #[cfg(feature = "saturating")]
+        fn test_state_saturating() {
+            let state = State(<$usize>::new((MAX_COUNT - 1) * RC_UNIT));
+
+            state.inc();
+            assert_eq!(state.load().count(), MAX_COUNT);
+
+            state.inc();
+            assert_eq!(state.load().count(), MAX_COUNT);
+
+            // Saturated count never reaches zero
+            assert!(!state.dec());
+            assert_eq!(state.load().count(), MAX_COUNT);
+
+            state.borrow();
+            state.unborrow();
+            assert_eq!(state.load().count(), MAX_COUNT);
+        }
     };
 }
 
@@ -3,18 +3,36 @@ use std::fmt::Debug;
 mod macros;
 pub(crate) use macros::*;
 
-/// Mask for reference count. It's also the maximum reference count (RC_MAX) we
-/// can have, since the last bit is used for borrow flags.
-pub const RC_MASK: usize = !0b1;
+/// Mask for reference count, since the last two bits are used for flags.
+pub const RC_MASK: usize = !0b11;
 /// One unit of reference count
-pub const RC_UNIT: usize = 0b10;
+pub const RC_UNIT: usize = 0b100;
 /// Mask for extracting borrowed bits
 pub const BORROW_MASK: usize = 0b1;
+/// Mask for the flag telling that the allocation is not owned by a `Box`, but
+/// handed back through the release header stored right before it. This never
+/// changes after construction.
+pub const EXTERNAL_MASK: usize = 0b10;
+/// Maximum reference count.
+#[cfg(not(feature = "abort-on-overflow"))]
+pub const MAX_COUNT: usize = RC_MASK / RC_UNIT;
+/// Maximum reference count. With the `abort-on-overflow` feature, only half of
+/// the bits available are used so that racing increments past it are detected
+/// long before the count could wrap around.
+#[cfg(feature = "abort-on-overflow")]
+pub const MAX_COUNT: usize = (RC_MASK / RC_UNIT) >> 1;
+
+/// Pattern written over the header of freed cells in debug builds, see
+/// `State::check_poison`.
+pub const POISON: usize = usize::from_ne_bytes([0xde; size_of::<usize>()]);
 
 /// Snapshot of the current state.
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(transparent)]
-pub struct Snapshot(pub usize);
+pub struct Snapshot(
+    /// The raw state word.
+    pub usize,
+);
 
 impl Debug for Snapshot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -28,16 +46,23 @@ impl Debug for Snapshot {
 impl Snapshot {
     /// Current reference count.
     pub fn count(&self) -> usize {
-        (self.0 & RC_MASK) >> 1
+        (self.0 & RC_MASK) / RC_UNIT
     }
 
+    /// Whether there is more than one owner.
     pub fn is_shared(&self) -> bool {
         self.count() > 1
     }
 
+    /// Whether the borrow flag is set.
     pub fn is_borrowed(&self) -> bool {
         (self.0 & BORROW_MASK) != 0
     }
+
+    /// Whether the allocation is not owned by a `Box`, see [`EXTERNAL_MASK`].
+    pub fn is_external(&self) -> bool {
+        (self.0 & EXTERNAL_MASK) != 0
+    }
 }
 
 impl From<usize> for Snapshot {
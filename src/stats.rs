@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Global counters of live `ThinCell` allocations, returned by [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Number of allocations not freed yet, from both `sync` and `unsync`
+    /// cells.
+    pub live: usize,
+    /// Total [`allocated_size`](crate::unsync::ThinCell::allocated_size) of
+    /// these allocations.
+    pub bytes: usize,
+}
+
+/// Returns the current allocation counters.
+///
+/// Only available with the `stats` feature. The counters are global, so
+/// concurrently running tests observe each other's cells.
+///
+/// # Examples
+///
+/// ```
+/// use thin_cell::{stats, unsync::ThinCell};
+///
+/// let cell = ThinCell::new(0u64);
+/// let stats = stats();
+/// assert!(stats.live >= 1);
+/// assert!(stats.bytes >= cell.allocated_size());
+/// ```
+pub fn stats() -> Stats {
+    Stats {
+        live: LIVE.load(Relaxed),
+        bytes: BYTES.load(Relaxed),
+    }
+}
+
+pub(crate) fn allocated(size: usize) {
+    LIVE.fetch_add(1, Relaxed);
+    BYTES.fetch_add(size, Relaxed);
+}
+
+pub(crate) fn freed(size: usize) {
+    LIVE.fetch_sub(1, Relaxed);
+    BYTES.fetch_sub(size, Relaxed);
+}
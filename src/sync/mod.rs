@@ -1,12 +1,86 @@
 //! Multithreaded version of `ThinCell`
 
 mod state;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    sync::{LazyLock, OnceLock},
+};
+
 use state::*;
+use synchrony::sync::{mutex_blocking::Mutex, shared::Shared};
 
 crate::thin_cell! {
     /// A compact (`1-usize`), multi-threaded smart pointer combining `Arc`
     /// and `Mutex`.
 }
 
+crate::slab::thin_slab!();
+crate::pool::thin_pool!();
+crate::slice::thin_slice_view!();
+crate::project::projected_cell!();
+crate::header::header_cell!();
+crate::cancel::thin_cancel!();
+crate::cow::thin_cow!();
+crate::erased::thin_erased!(+ Send + Sync);
+crate::interner::thin_interner!();
+crate::registry::thin_registry!(+ Send + Sync);
+crate::any_map::thin_any_map!(Any + Send + Sync);
+#[cfg(feature = "erased-serde")]
+crate::serde_any::thin_serde_registry!(Any + Send + Sync);
+crate::error::thin_error!(+ Send + Sync);
+crate::raw::thin_raw!();
+crate::task::thin_task!(+ Send);
+crate::once::thin_once!(OnceLock, LazyLock);
+
 unsafe impl<T: ?Sized + Send + Sync> Send for ThinCell<T> {}
 unsafe impl<T: ?Sized + Send + Sync> Sync for ThinCell<T> {}
+
+unsafe impl<T: Send + Sync> Send for ThinSlab<T> {}
+unsafe impl<T: Send + Sync> Sync for ThinSlab<T> {}
+
+// Like `Ref`, which holds a `&mut U`
+unsafe impl<T: ?Sized + Send + Sync, U: ?Sized + Send> Send for OwnedRef<T, U> {}
+unsafe impl<T: ?Sized + Send + Sync, U: ?Sized + Sync> Sync for OwnedRef<T, U> {}
+
+// Only handles of `Send + Sync` values are leaked into it
+unsafe impl Send for LeakRegistry {}
+unsafe impl Sync for LeakRegistry {}
+
+// The future and output are only accessed under the borrow flag, like a `Mutex`
+unsafe impl<T: Send> Send for ThinTask<T> {}
+unsafe impl<T: Send> Sync for ThinTask<T> {}
+
+impl<T: ?Sized> ThinCell<T> {
+    /// Converts the cell into an [`unsync::ThinCell`](crate::unsync::ThinCell)
+    /// in place, the reverse of
+    /// [`unsync::ThinCell::into_shared`](crate::unsync::ThinCell::into_shared).
+    ///
+    /// Returns `Err(self)` if there are other owners, it is currently borrowed,
+    /// or its allocation is external, e.g. from
+    /// [`new_pair`](ThinCell::new_pair) or a [`ThinSlab`], which other threads
+    /// may still release.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use thin_cell::sync::ThinCell;
+    /// let cell = ThinCell::new(1);
+    /// let other = cell.clone();
+    /// let cell = cell.into_local().unwrap_err();
+    ///
+    /// drop(other);
+    /// let local = cell.into_local().unwrap();
+    /// assert_eq!(*local.borrow(), 1);
+    /// ```
+    pub fn into_local(self) -> Result<crate::unsync::ThinCell<T>, Self> {
+        let state = self.state().load();
+        if state.is_shared() || state.is_borrowed() || state.is_external() {
+            return Err(self);
+        }
+
+        // SAFETY: The handle is unique, not borrowed, and owns its allocation,
+        // and the `unsync` flavour uses the same allocation layout and state bits.
+        Ok(unsafe { crate::unsync::ThinCell::from_raw(self.leak()) })
+    }
+}
@@ -1,4 +1,8 @@
-use synchrony::sync::atomic::AtomicUsize;
+// Model tests replace the state with loom's, see `tests/loom.rs`
+#[cfg(thin_cell_loom)]
+use loom::{sync::atomic::AtomicUsize, thread::yield_now};
+#[cfg(not(thin_cell_loom))]
+use {std::thread::yield_now, synchrony::sync::atomic::AtomicUsize};
 
 use crate::state::*;
 
@@ -20,9 +24,9 @@ impl State {
             let new = curr | BORROW_MASK;
 
             match self.0.compare_exchange_weak(old, new, Acquire, Relaxed) {
-                Ok(_) => return,
+                Ok(orig) => return validate(orig),
                 Err(actual) => {
-                    std::thread::yield_now();
+                    yield_now();
                     curr = actual;
                     continue;
                 }
@@ -30,6 +34,16 @@ impl State {
         }
     }
 
+    /// Borrowing never panics, so there's nothing to report.
+    #[cfg(feature = "track-borrow")]
+    pub fn track(&self, _: &'static std::panic::Location<'static>) {}
+
+    #[cfg(feature = "track-borrow")]
+    pub fn untrack(&self) {}
+
+    #[cfg(feature = "borrow-history")]
+    pub fn forget(&self) {}
+
     /// Tries to set the borrow bit. Returns `true` if successful, `false` if
     /// already borrowed.
     #[inline]
@@ -42,8 +56,10 @@ impl State {
                     Some(curr | BORROW_MASK) // Set borrow bit
                 }
             })
+            .inspect(|&orig| validate(orig))
             .is_ok()
     }
 }
 
+#[cfg(not(thin_cell_loom))]
 test_cases!(AtomicUsize);
@@ -0,0 +1,148 @@
+macro_rules! thin_task {
+    ($($bound:tt)*) => {
+        /// State of a [`ThinTask`]: the future until it completes, then its output.
+        struct TaskSlot<T, F: ?Sized> {
+            output: Option<T>,
+            finished: bool,
+            future: ManuallyDrop<F>,
+        }
+
+        impl<T, F: ?Sized> Drop for TaskSlot<T, F> {
+            fn drop(&mut self) {
+                if !self.finished {
+                    // SAFETY: The future is dropped here or when it finishes, never
+                    // both.
+                    unsafe { ManuallyDrop::drop(&mut self.future) }
+                }
+            }
+        }
+
+        /// A future pinned in a thin cell, with its output stored in place once it
+        /// completes.
+        ///
+        /// This is the task object of a simple executor: handles are cheap to
+        /// clone into run queues and wakers, [`poll`](ThinTask::poll) drives the
+        /// future, and the output is taken out with
+        /// [`take_output`](ThinTask::take_output). The future is dropped as soon
+        /// as it completes, and is never moved.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use thin_cell::unsync::ThinTask;
+        /// use std::task::{Poll, Waker};
+        ///
+        /// let task = ThinTask::new(async { 1 + 1 });
+        /// assert!(!task.is_finished());
+        ///
+        /// assert_eq!(task.poll(Waker::noop()), Poll::Ready(()));
+        /// assert!(task.is_finished());
+        /// assert_eq!(task.take_output(), Some(2));
+        /// assert_eq!(task.take_output(), None);
+        /// ```
+        pub struct ThinTask<T> {
+            cell: ThinCell<TaskSlot<T, dyn Future<Output = T> $($bound)*>>,
+        }
+
+        impl<T> ThinTask<T> {
+            /// Creates a new task driving `future`.
+            #[cfg_attr(any(feature = "debug", feature = "profile"), track_caller)]
+            pub fn new<F: Future<Output = T> $($bound)* + 'static>(future: F) -> Self {
+                let slot = TaskSlot {
+                    output: None,
+                    finished: false,
+                    future: ManuallyDrop::new(future),
+                };
+                ThinTask {
+                    // SAFETY: unsized coercion from `F` to `dyn Future` is safe
+                    cell: unsafe { ThinCell::new_unsize(slot, |ptr| ptr as _) },
+                }
+            }
+
+            /// Polls the future with `waker` unless it has already completed, in
+            /// which case this returns `Poll::Ready(())` right away.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the task is being
+            /// polled or accessed through another handle, e.g. when the future
+            /// polls its own task.
+            #[track_caller]
+            pub fn poll(&self, waker: &std::task::Waker) -> std::task::Poll<()> {
+                let mut slot = self.cell.borrow();
+                let slot = &mut *slot;
+                if slot.finished {
+                    return std::task::Poll::Ready(());
+                }
+
+                // SAFETY: The future is never moved, as the cell is only reachable
+                // through `ThinTask` handles, and it is dropped in place.
+                let future = unsafe { std::pin::Pin::new_unchecked(&mut *slot.future) };
+                match future.poll(&mut std::task::Context::from_waker(waker)) {
+                    std::task::Poll::Ready(output) => {
+                        slot.output = Some(output);
+                        slot.finished = true;
+                        // SAFETY: The future completed and is marked as dropped
+                        unsafe { ManuallyDrop::drop(&mut slot.future) };
+                        std::task::Poll::Ready(())
+                    }
+                    std::task::Poll::Pending => std::task::Poll::Pending,
+                }
+            }
+
+            /// Returns `true` if the future has completed.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the task is being
+            /// polled.
+            #[track_caller]
+            pub fn is_finished(&self) -> bool {
+                self.cell.borrow().finished
+            }
+
+            /// Takes the output of the future, if it has completed and the output
+            /// was not taken yet.
+            ///
+            /// # Panics
+            ///
+            /// Panics (or blocks for the `sync` flavour) if the task is being
+            /// polled.
+            #[track_caller]
+            pub fn take_output(&self) -> Option<T> {
+                self.cell.borrow().output.take()
+            }
+
+            /// Returns the number of handles of the task.
+            pub fn count(&self) -> usize {
+                self.cell.count()
+            }
+
+            /// Returns `true` if the two handles point to the same task.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                self.cell.ptr_eq(&other.cell)
+            }
+        }
+
+        impl<T> Clone for ThinTask<T> {
+            fn clone(&self) -> Self {
+                ThinTask {
+                    cell: self.cell.clone(),
+                }
+            }
+        }
+
+        impl<T> Debug for ThinTask<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut d = f.debug_struct("ThinTask");
+                match self.cell.try_borrow() {
+                    Some(slot) => d.field("finished", &slot.finished),
+                    None => d.field("finished", &format_args!("<borrowed>")),
+                }
+                .finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+pub(crate) use thin_task;
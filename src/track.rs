@@ -0,0 +1,142 @@
+//! Side table recording where the outstanding borrow of each unsync cell was
+//! created, so that borrow conflicts can point at it. With the
+//! `borrow-history` feature, the last few borrow events of each cell are kept
+//! as well.
+//!
+//! As unsync cells never leave their thread, the table is thread-local. Once
+//! it has been destroyed, e.g. when cells stored in other thread-locals are
+//! dropped at thread exit, nothing is recorded anymore.
+
+use std::{cell::RefCell, collections::BTreeMap, panic::Location};
+#[cfg(feature = "borrow-history")]
+use std::{collections::VecDeque, fmt, time::Instant};
+
+/// Number of events kept per cell.
+#[cfg(feature = "borrow-history")]
+const HISTORY_LEN: usize = 8;
+
+#[derive(Default)]
+struct Entry {
+    outstanding: Option<&'static Location<'static>>,
+    #[cfg(feature = "borrow-history")]
+    history: VecDeque<BorrowEvent>,
+}
+
+thread_local! {
+    /// Tracked cells, keyed by the address of their state.
+    static CELLS: RefCell<BTreeMap<usize, Entry>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Kind of a [`BorrowEvent`].
+#[cfg(feature = "borrow-history")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowEventKind {
+    /// The cell was borrowed.
+    Borrow,
+    /// The guard was dropped.
+    Release,
+}
+
+/// An entry in the borrow history of a cell.
+#[cfg(feature = "borrow-history")]
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowEvent {
+    /// What happened.
+    pub kind: BorrowEventKind,
+    /// Where the borrow was created. For releases, this is the location of the
+    /// borrow being released.
+    pub location: &'static Location<'static>,
+    /// When it happened.
+    pub at: Instant,
+}
+
+#[cfg(feature = "borrow-history")]
+impl fmt::Display for BorrowEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            BorrowEventKind::Borrow => "borrow",
+            BorrowEventKind::Release => "release",
+        };
+        write!(
+            f,
+            "{kind} of {} ({:?} ago)",
+            self.location,
+            self.at.elapsed()
+        )
+    }
+}
+
+#[cfg(feature = "borrow-history")]
+impl Entry {
+    fn record(&mut self, kind: BorrowEventKind, location: &'static Location<'static>) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(BorrowEvent {
+            kind,
+            location,
+            at: Instant::now(),
+        });
+    }
+}
+
+fn key<S>(state: &S) -> usize {
+    (state as *const S).addr()
+}
+
+pub fn borrowed<S>(state: &S, location: &'static Location<'static>) {
+    let _ = CELLS.try_with(|cells| {
+        let mut cells = cells.borrow_mut();
+        let entry = cells.entry(key(state)).or_default();
+        entry.outstanding = Some(location);
+        #[cfg(feature = "borrow-history")]
+        entry.record(BorrowEventKind::Borrow, location);
+    });
+}
+
+pub fn released<S>(state: &S) {
+    let _ = CELLS.try_with(|cells| {
+        let mut cells = cells.borrow_mut();
+        #[cfg(feature = "borrow-history")]
+        if let Some(entry) = cells.get_mut(&key(state))
+            && let Some(location) = entry.outstanding.take()
+        {
+            entry.record(BorrowEventKind::Release, location);
+        }
+
+        // Without history there's nothing left to keep
+        #[cfg(not(feature = "borrow-history"))]
+        cells.remove(&key(state));
+    });
+}
+
+pub fn borrowed_at<S>(state: &S) -> Option<&'static Location<'static>> {
+    CELLS
+        .try_with(|cells| {
+            let cells = cells.borrow();
+            cells.get(&key(state)).and_then(|entry| entry.outstanding)
+        })
+        .ok()
+        .flatten()
+}
+
+/// Returns the recorded events of a cell, oldest first.
+#[cfg(feature = "borrow-history")]
+pub fn history<S>(state: &S) -> Vec<BorrowEvent> {
+    CELLS
+        .try_with(|cells| {
+            let cells = cells.borrow();
+            cells
+                .get(&key(state))
+                .map(|entry| entry.history.iter().copied().collect())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+/// Drops everything recorded about a cell being deallocated, so that a new
+/// cell at the same address starts afresh.
+#[cfg(feature = "borrow-history")]
+pub fn forget<S>(state: &S) {
+    let _ = CELLS.try_with(|cells| cells.borrow_mut().remove(&key(state)));
+}
@@ -1,9 +1,187 @@
 //! Singlethreaded version of `ThinCell`
 
+pub mod oneshot;
 mod state;
+use std::{
+    cell::{LazyCell, OnceCell},
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+};
+
 use state::*;
+pub use state::{BorrowConflict, set_borrow_conflict_hook, take_borrow_conflict_hook};
+use synchrony::unsync::{mutex_blocking::Mutex, shared::Shared};
 
 crate::thin_cell! {
     /// A compact (`1-usize`), single-threaded smart pointer combining `Rc`
     /// and `RefCell` with only `borrow_mut`.
 }
+
+crate::slab::thin_slab!();
+crate::pool::thin_pool!();
+crate::slice::thin_slice_view!();
+crate::project::projected_cell!();
+crate::header::header_cell!();
+crate::cancel::thin_cancel!();
+crate::cow::thin_cow!();
+crate::erased::thin_erased!();
+crate::interner::thin_interner!();
+crate::registry::thin_registry!();
+crate::any_map::thin_any_map!(Any);
+#[cfg(feature = "erased-serde")]
+crate::serde_any::thin_serde_registry!(Any);
+crate::error::thin_error!();
+crate::raw::thin_raw!();
+crate::task::thin_task!();
+crate::once::thin_once!(OnceCell, LazyCell);
+
+// Without threads, values can't actually be sent or shared between threads, so
+// this only satisfies the `Send` bounds of async runtimes and frameworks.
+#[cfg(all(
+    feature = "wasm-send",
+    target_arch = "wasm32",
+    target_os = "unknown",
+    not(target_feature = "atomics")
+))]
+unsafe impl<T: ?Sized + Send> Send for ThinCell<T> {}
+#[cfg(all(
+    feature = "wasm-send",
+    target_arch = "wasm32",
+    target_os = "unknown",
+    not(target_feature = "atomics")
+))]
+unsafe impl<T: ?Sized + Send> Sync for ThinCell<T> {}
+
+impl<T: ?Sized> ThinCell<T> {
+    /// Converts the cell into a [`sync::ThinCell`](crate::sync::ThinCell) in
+    /// place, so it can cross threads.
+    ///
+    /// Both flavours share the same layout, so this only switches to atomic
+    /// state operations. Returns `Err(self)` if there are other owners, it is
+    /// currently borrowed, or its allocation is external, e.g. from
+    /// [`new_pair`](ThinCell::new_pair) or a [`ThinSlab`], whose release is not
+    /// thread-safe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use thin_cell::unsync::ThinCell;
+    /// let cell = ThinCell::new(vec![1]);
+    /// cell.borrow().push(2);
+    ///
+    /// let shared = cell.into_shared().unwrap();
+    /// let shared = std::thread::spawn(move || {
+    ///     shared.borrow().push(3);
+    ///     shared
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// assert_eq!(*shared.into_local().unwrap().borrow(), [1, 2, 3]);
+    /// ```
+    pub fn into_shared(self) -> Result<crate::sync::ThinCell<T>, Self> {
+        let state = self.state().load();
+        if state.is_shared() || state.is_borrowed() || state.is_external() {
+            return Err(self);
+        }
+
+        // The `sync` flavour keeps no history, so it would never be forgotten
+        #[cfg(feature = "borrow-history")]
+        self.state().forget();
+
+        // SAFETY: The handle is unique, not borrowed, and owns its allocation,
+        // and the `sync` flavour uses the same allocation layout and state bits.
+        Ok(unsafe { crate::sync::ThinCell::from_raw(self.leak()) })
+    }
+
+    /// Turns the cell into a [`SendUnique`] token that can be moved to another
+    /// thread and turned back into a cell there.
+    ///
+    /// Unlike [`into_shared`](ThinCell::into_shared), the cell stays in this
+    /// flavour, so there is no atomic state operation on either side. Returns
+    /// `Err(self)` if there are other owners, it is currently borrowed, or its
+    /// allocation is external, like for [`into_shared`](ThinCell::into_shared).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use thin_cell::unsync::ThinCell;
+    /// let cell = ThinCell::new(vec![1]);
+    /// let other = cell.clone();
+    ///
+    /// let cell = cell.into_send().unwrap_err();
+    /// drop(other);
+    ///
+    /// let token = cell.into_send().unwrap();
+    /// std::thread::spawn(move || {
+    ///     let cell = token.into_inner();
+    ///     cell.borrow().push(2);
+    ///     assert_eq!(*cell.borrow(), [1, 2]);
+    /// })
+    /// .join()
+    /// .unwrap();
+    /// ```
+    pub fn into_send(self) -> Result<SendUnique<T>, Self>
+    where
+        T: Send,
+    {
+        let state = self.state().load();
+        if state.is_shared() || state.is_borrowed() || state.is_external() {
+            return Err(self);
+        }
+
+        // Histories are kept per thread, and the cell may not come back to this one
+        #[cfg(feature = "borrow-history")]
+        self.state().forget();
+
+        Ok(SendUnique { cell: self })
+    }
+}
+
+/// A unique, unborrowed [`ThinCell`] that can be sent to another thread,
+/// created by [`ThinCell::into_send`].
+///
+/// The cell can only be taken back out with
+/// [`into_inner`](SendUnique::into_inner), so no handle is left on the sending
+/// thread.
+pub struct SendUnique<T: ?Sized> {
+    cell: ThinCell<T>,
+}
+
+// The token owns the only handle of the cell, which is not borrowed and owns
+// its allocation rather than releasing it through a non-thread-safe external
+// header, so the whole allocation moves along with it like a `Box<T>`.
+unsafe impl<T: ?Sized + Send> Send for SendUnique<T> {}
+
+impl<T: ?Sized> SendUnique<T> {
+    /// Consumes the token and returns the cell, on the current thread.
+    pub fn into_inner(self) -> ThinCell<T> {
+        self.cell
+    }
+}
+
+impl<T: ?Sized> From<SendUnique<T>> for ThinCell<T> {
+    fn from(token: SendUnique<T>) -> Self {
+        token.into_inner()
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for SendUnique<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendUnique").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "borrow-history")]
+pub use crate::track::{BorrowEvent, BorrowEventKind};
+
+#[cfg(feature = "borrow-history")]
+impl<T: ?Sized> ThinCell<T> {
+    /// Returns the last few borrow and release events of this cell, oldest
+    /// first.
+    ///
+    /// Only available with the `borrow-history` feature.
+    pub fn borrow_history(&self) -> Vec<BorrowEvent> {
+        crate::track::history(self.state())
+    }
+}
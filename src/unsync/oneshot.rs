@@ -0,0 +1,165 @@
+//! A single-threaded oneshot channel sharing one `ThinCell` allocation.
+//!
+//! The value slot, the waker of the receiver and the closed flag live in the
+//! same cell, so handing a completion over to a task costs one allocation.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::{
+//!     future::Future,
+//!     pin::pin,
+//!     task::{Context, Poll, Waker},
+//! };
+//!
+//! use thin_cell::unsync::oneshot;
+//!
+//! let (tx, rx) = oneshot::channel();
+//! let mut rx = pin!(rx);
+//! let mut cx = Context::from_waker(Waker::noop());
+//! assert!(rx.as_mut().poll(&mut cx).is_pending());
+//!
+//! tx.send(5).unwrap();
+//! assert_eq!(rx.poll(&mut cx), Poll::Ready(Ok(5)));
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use super::ThinCell;
+
+struct Slot<T> {
+    msg: Option<T>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// Creates a new oneshot channel, returning its two halves.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let cell = ThinCell::new(Slot {
+        msg: None,
+        waker: None,
+        closed: false,
+    });
+    (Sender { cell: cell.clone() }, Receiver { cell })
+}
+
+/// The sending half of a [`channel`].
+pub struct Sender<T> {
+    cell: ThinCell<Slot<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `value` to the receiver and wakes it, returning `Err(value)` if
+    /// the receiver was dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut slot = self.cell.borrow();
+        if slot.closed {
+            return Err(value);
+        }
+        slot.msg = Some(value);
+        let waker = slot.waker.take();
+        drop(slot);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver was dropped.
+    pub fn is_closed(&self) -> bool {
+        self.cell.borrow().closed
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut slot = self.cell.borrow();
+        slot.closed = true;
+        let waker = slot.waker.take();
+        drop(slot);
+
+        // Wakes the receiver to report the closed channel if nothing was sent
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a [`channel`], a future resolving to the sent value.
+pub struct Receiver<T> {
+    cell: ThinCell<Slot<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Takes the sent value without waiting, returning `Ok(None)` if it was
+    /// not sent yet.
+    ///
+    /// Returns [`RecvError`] if the sender was dropped without sending.
+    pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
+        let mut slot = self.cell.borrow();
+        match slot.msg.take() {
+            Some(value) => Ok(Some(value)),
+            None if slot.closed => Err(RecvError(())),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.cell.borrow();
+        if let Some(value) = slot.msg.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if slot.closed {
+            return Poll::Ready(Err(RecvError(())));
+        }
+
+        match &mut slot.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker => *waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut slot = self.cell.borrow();
+        slot.closed = true;
+        slot.waker = None;
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by a [`Receiver`] whose [`Sender`] was dropped without
+/// sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl std::error::Error for RecvError {}
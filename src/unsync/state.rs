@@ -1,7 +1,57 @@
+use std::{cell::Cell, panic::Location};
+
 use synchrony::unsync::atomic::AtomicUsize;
 
 use crate::state::*;
 
+/// A conflicting call to `ThinCell::borrow`, passed to the hook installed with
+/// [`set_borrow_conflict_hook`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct BorrowConflict {
+    /// Address of the cell, as returned by
+    /// [`ThinCell::addr`](super::ThinCell::addr).
+    pub addr: usize,
+    /// Where the conflicting borrow was attempted.
+    pub location: &'static Location<'static>,
+}
+
+thread_local! {
+    static HOOK: Cell<Option<fn(&BorrowConflict)>> = const { Cell::new(None) };
+}
+
+/// Installs a hook called on borrow conflicts of the current thread, right
+/// before `ThinCell::borrow` panics, replacing the previous one.
+///
+/// The hook can emit diagnostics or abort the process instead of panicking.
+/// It is thread-local, like the cells it reports on.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use thin_cell::unsync::{BorrowConflict, ThinCell, set_borrow_conflict_hook};
+///
+/// set_borrow_conflict_hook(|conflict: &BorrowConflict| {
+///     eprintln!(
+///         "cell {:#x} borrowed twice at {}",
+///         conflict.addr, conflict.location
+///     );
+/// });
+///
+/// let cell = ThinCell::new(1);
+/// let _guard = cell.borrow();
+/// cell.borrow(); // calls the hook, then panics
+/// ```
+pub fn set_borrow_conflict_hook(hook: fn(&BorrowConflict)) {
+    HOOK.set(Some(hook));
+}
+
+/// Removes the hook installed with [`set_borrow_conflict_hook`] on the current
+/// thread and returns it.
+pub fn take_borrow_conflict_hook() -> Option<fn(&BorrowConflict)> {
+    HOOK.take()
+}
+
 impl_state! {
     /// Unsynchronized and intended for single-threaded use.
     struct State(AtomicUsize);
@@ -10,15 +60,70 @@ impl_state! {
 impl State {
     pub fn acquire(&self) {}
 
+    #[track_caller]
     pub fn borrow(&self) {
         let curr = self.load().0;
         if (curr & BORROW_MASK) != 0 {
-            panic!("Already borrowed");
+            self.already_borrowed();
         } else {
+            validate(curr);
             self.0.store(curr | BORROW_MASK, Release);
         }
     }
 
+    /// Records where the current borrow was created.
+    #[cfg(feature = "track-borrow")]
+    pub fn track(&self, location: &'static std::panic::Location<'static>) {
+        crate::track::borrowed(self, location);
+    }
+
+    #[cfg(feature = "track-borrow")]
+    pub fn untrack(&self) {
+        crate::track::released(self);
+    }
+
+    /// Forgets the history of a cell being deallocated.
+    #[cfg(feature = "borrow-history")]
+    pub fn forget(&self) {
+        crate::track::forget(self);
+    }
+
+    #[cold]
+    #[track_caller]
+    fn already_borrowed(&self) -> ! {
+        let addr = (self as *const Self).addr() - crate::raw::STATE_OFFSET;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(addr, location = %Location::caller(), "borrow conflict");
+
+        if let Some(hook) = HOOK.get() {
+            hook(&BorrowConflict {
+                addr,
+                location: Location::caller(),
+            });
+        }
+
+        #[cfg(feature = "track-borrow")]
+        if let Some(location) = crate::track::borrowed_at(self) {
+            #[cfg(feature = "borrow-history")]
+            {
+                let history = crate::track::history(self)
+                    .iter()
+                    .map(|event| format!("\n  {event}"))
+                    .collect::<String>();
+                panic!(
+                    "Already borrowed (outstanding borrow created at {location}), recent \
+                     events:{history}"
+                );
+            }
+
+            #[cfg(not(feature = "borrow-history"))]
+            panic!("Already borrowed (outstanding borrow created at {location})");
+        }
+
+        panic!("Already borrowed");
+    }
+
     /// Tries to set the borrow bit. Returns `true` if successful, `false` if
     /// already borrowed.
     #[inline]
@@ -27,7 +132,8 @@ impl State {
             return false;
         }
 
-        self.0.fetch_or(BORROW_MASK, Release);
+        let orig = self.0.fetch_or(BORROW_MASK, Release);
+        validate(orig);
         true
     }
 }
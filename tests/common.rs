@@ -28,6 +28,45 @@ macro_rules! testcases {
             assert_eq!(*cell.borrow(), 42);
         }
 
+        #[test]
+        fn test_thin_cell_new_with() {
+            let cell = unsafe {
+                ThinCell::<[u64; 4]>::new_with(|data| {
+                    data.write([1, 2, 3, 4]);
+                })
+            };
+            assert_eq!(*cell.borrow(), [1, 2, 3, 4]);
+
+            let res = unsafe {
+                ThinCell::<String>::try_new_with(|data| {
+                    data.write(String::from("ok"));
+                    Ok::<_, ()>(())
+                })
+            };
+            assert_eq!(*res.unwrap().borrow(), "ok");
+
+            let res = unsafe { ThinCell::<String>::try_new_with(|_| Err("failed")) };
+            assert_eq!(res.unwrap_err(), "failed");
+        }
+
+        #[test]
+        fn test_thin_cell_new_aligned() {
+            for align in [1, 16, 512, 4096] {
+                let cell = ThinCell::new_aligned(7u32, align);
+                assert_eq!((&raw const *cell.borrow()).addr() % align, 0);
+                assert_eq!(cell.clone().try_unwrap().unwrap_err().count(), 2);
+                assert_eq!(cell.try_unwrap().unwrap(), 7);
+
+                let buf = ThinCell::<[u8]>::new_aligned_zeroed(100, align);
+                assert_eq!(buf.borrow().as_ptr().addr() % align, 0);
+                assert!(buf.borrow().iter().all(|&b| b == 0));
+                assert_eq!(buf.into_vec().unwrap().len(), 100);
+            }
+
+            let buf = ThinCell::<[u8]>::new_aligned_zeroed(0, 64);
+            assert!(buf.borrow().is_empty());
+        }
+
         #[test]
         fn test_thin_cell_borrow_read() {
             let cell = ThinCell::new(100);
@@ -154,6 +193,42 @@ macro_rules! testcases {
             assert!(flag.get() == 1);
         }
 
+        #[test]
+        #[cfg(not(feature = "abort-on-borrowed-drop"))]
+        fn test_thin_cell_drop_while_borrowed() {
+            struct DropFlag<'a>(&'a Cell<usize>, u64);
+
+            impl<'a> Drop for DropFlag<'a> {
+                fn drop(&mut self) {
+                    self.0.update(|x| x + 1);
+                }
+            }
+
+            let flag = Cell::new(0);
+
+            let cell = ThinCell::new(DropFlag(&flag, 42));
+            let other = cell.clone();
+            let guard = cell.borrow();
+            let value: *const DropFlag = &*guard;
+
+            // Safe code can drop every owner while the value is still borrowed
+            std::mem::forget(guard);
+            drop(other);
+            drop(cell);
+
+            // The allocation is leaked: the value is neither dropped nor freed
+            assert_eq!(flag.get(), 0);
+            assert_eq!(unsafe { (*value).1 }, 42);
+        }
+
+        #[test]
+        fn test_thin_cell_try_new() {
+            let cell = ThinCell::try_new(vec![1]).unwrap();
+            cell.borrow().push(2);
+            assert_eq!(*cell.borrow(), [1, 2]);
+            assert_eq!(cell.count(), 1);
+        }
+
         #[test]
         fn test_thin_cell_ref_deref() {
             let cell = ThinCell::new([1, 2, 3, 4, 5]);
@@ -181,6 +256,45 @@ macro_rules! testcases {
             assert_eq!(borrowed[0], 10);
         }
 
+        #[test]
+        fn test_thin_cell_ref_as_ref_and_borrow() {
+            fn push(mut v: impl AsMut<Vec<i32>>) {
+                v.as_mut().push(1);
+            }
+
+            fn len(v: impl std::borrow::BorrowMut<Vec<i32>>) -> usize {
+                v.borrow().len()
+            }
+
+            let cell = ThinCell::new(vec![]);
+            push(cell.borrow());
+            assert_eq!(cell.borrow().as_ref(), &[1]);
+            assert_eq!(len(cell.borrow()), 1);
+        }
+
+        #[test]
+        fn test_thin_cell_borrow_debug_checked() {
+            let cell = ThinCell::new(vec![1]);
+            unsafe { cell.borrow_debug_checked() }.push(2);
+            assert_eq!(*cell.borrow(), [1, 2]);
+
+            // Borrowing again would be UB in release builds
+            if cfg!(debug_assertions) {
+                let guard = unsafe { cell.borrow_debug_checked() };
+                assert!(cell.try_borrow().is_none());
+                drop(guard);
+            }
+            assert!(cell.try_borrow().is_some());
+
+            // In release builds, the guard leaves the unset flag alone when dropped,
+            // which `cfg(thin_cell_validate)` would report
+            let mut guard = unsafe { cell.borrow_debug_checked() };
+            guard.push(3);
+            assert_eq!(format!("{guard:?}"), "[1, 2, 3]");
+            drop(guard);
+            assert_eq!(*cell.borrow(), [1, 2, 3]);
+        }
+
         #[test]
         fn test_thin_cell_leak_and_from_raw() {
             let cell = ThinCell::new(42);
@@ -269,6 +383,25 @@ macro_rules! testcases {
             cell_concrete.borrow().greet();
         }
 
+        #[test]
+        fn test_unsize_shared_same_metadata() {
+            let cell = ThinCell::new([1u8, 2]).unsize_slice();
+            let array = unsafe { cell.clone().downcast_unchecked::<[u8; 2]>() };
+            let _guard = cell.borrow();
+
+            let slice = unsafe { array.unsize::<[u8]>(|p| p as _) };
+            assert!(slice.ptr_eq(&cell));
+            assert_eq!(slice.count(), 2);
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot coerce shared `ThinCell`")]
+        fn test_unsize_shared_other_metadata() {
+            let cell = ThinCell::new([1u8, 2]).unsize_slice();
+            let array = unsafe { cell.clone().downcast_unchecked::<[u8; 1]>() };
+            let _ = unsafe { array.unsize::<[u8]>(|p| p as _) };
+        }
+
         #[test]
         fn test_downcast() {
             use std::any::Any;
@@ -302,6 +435,1316 @@ macro_rules! testcases {
             let cell = ThinCell::new([1, 1, 4, 5, 1, 4]).unsize_slice();
             assert!(cell.borrow().len() == 6);
         }
+
+        #[test]
+        fn test_new_many() {
+            struct DropFlag<'a>(&'a Cell<usize>, u64);
+
+            impl<'a> Drop for DropFlag<'a> {
+                fn drop(&mut self) {
+                    self.0.update(|x| x + 1);
+                }
+            }
+
+            assert!(ThinCell::<u8>::new_many([]).is_empty());
+
+            let flag = Cell::new(0);
+            let mut cells = ThinCell::new_many((0..10).map(|i| DropFlag(&flag, i)));
+            assert_eq!(cells.len(), 10);
+
+            for (i, cell) in cells.iter().enumerate() {
+                assert_eq!(cell.count(), 1);
+                assert_eq!(cell.borrow().1, i as u64);
+            }
+
+            // Cells outlive the `Vec` and each other in any order
+            let kept = cells.remove(7);
+            let shared = cells[3].clone();
+            cells.swap_remove(0);
+            assert_eq!(flag.get(), 1);
+
+            let value = cells.remove(4).try_unwrap().ok().unwrap();
+            assert_eq!(value.1, 4);
+            drop(value);
+            assert_eq!(flag.get(), 2);
+
+            drop(cells);
+            assert_eq!(flag.get(), 8);
+            assert_eq!(shared.borrow().1, 3);
+
+            drop(shared);
+            assert_eq!(kept.borrow().1, 7);
+            drop(kept);
+            assert_eq!(flag.get(), 10);
+        }
+
+        #[test]
+        fn test_new_pair() {
+            #[repr(align(64))]
+            struct Wide(u8);
+
+            let (a, b) = ThinCell::new_pair(String::from("a"), Wide(1));
+            assert_eq!(b.borrow().0, 1);
+            assert_eq!(&*b.borrow() as *const Wide as usize % 64, 0);
+
+            // Both sides outlive each other in any order
+            let shared = a.clone();
+            let _guard = b.borrow();
+            drop(a);
+            assert_eq!(*shared.borrow(), "a");
+            assert_eq!(shared.try_unwrap().unwrap(), "a");
+            drop(_guard);
+            assert_eq!(b.try_unwrap().ok().unwrap().0, 1);
+        }
+
+        #[test]
+        fn test_new_many_unsize() {
+            let cells = ThinCell::new_many([[1u16, 2, 3], [4, 5, 6]]);
+            let slices = cells
+                .into_iter()
+                .map(ThinCell::unsize_slice)
+                .collect::<Vec<_>>();
+            assert_eq!(*slices[1].borrow(), [4, 5, 6]);
+
+            let cells = ThinCell::new_many([Robot { id: 1 }, Robot { id: 2 }]);
+            let dyns = cells
+                .into_iter()
+                .map(|cell| unsafe { cell.unsize::<dyn Greeter>(|p| p as _) })
+                .collect::<Vec<_>>();
+            dyns[1].borrow().set_id(3);
+            dyns[1].borrow().greet();
+        }
+
+        #[test]
+        fn test_slab() {
+            let mut slab = ThinSlab::new();
+            assert!(slab.is_empty());
+
+            let keys = (0..100).map(|i| slab.insert(i)).collect::<Vec<_>>();
+            assert_eq!(slab.len(), 100);
+            for (i, key) in keys.iter().enumerate() {
+                assert_eq!(*slab.get(*key).unwrap().borrow(), i);
+            }
+
+            // Removing only drops the slab's handle
+            let shared = slab.get(keys[10]).unwrap().clone();
+            let removed = slab.remove(keys[10]).unwrap();
+            assert!(removed.ptr_eq(&shared));
+            assert_eq!(removed.count(), 2);
+            assert!(!slab.contains(keys[10]));
+            assert!(slab.remove(keys[10]).is_none());
+            assert_eq!(slab.len(), 99);
+
+            // Slot is still in use, so it's not handed out again
+            let key = slab.insert(1000);
+            assert!(!slab.get(key).unwrap().ptr_eq(&shared));
+
+            drop(removed);
+            drop(shared);
+
+            // Now the slot is free, but the old key stays dead
+            slab.remove(key);
+            let key = slab.insert(2000);
+            assert_ne!(key, keys[10]);
+            assert!(slab.get(keys[10]).is_none());
+            assert_eq!(*slab.get(key).unwrap().borrow(), 2000);
+        }
+
+        #[test]
+        fn test_slab_cells_outlive_slab() {
+            let mut slab = ThinSlab::new();
+            let keys = (0..40).map(|i| slab.insert(vec![i])).collect::<Vec<_>>();
+            let cell = slab.get(keys[35]).unwrap().clone();
+            let unsized_cell = unsafe {
+                slab.remove(keys[3])
+                    .unwrap()
+                    .unsize::<dyn std::fmt::Debug>(|p| p as _)
+            };
+            drop(slab);
+
+            cell.borrow().push(1);
+            assert_eq!(*cell.borrow(), [35, 1]);
+            assert!(format!("{unsized_cell:?}").contains("[3]"));
+            assert_eq!(cell.try_unwrap().unwrap(), [35, 1]);
+        }
+
+        #[test]
+        fn test_allocated_size() {
+            let cell = ThinCell::new(0u64);
+            assert_eq!(cell.allocated_size(), size_of::<Inner<u64>>());
+
+            let cell = ThinCell::new([0u32; 5]).unsize_slice();
+            assert_eq!(cell.allocated_size(), size_of::<Inner<[u32; 5]>>());
+
+            let cell = unsafe { ThinCell::<dyn Greeter>::new_unsize(Robot { id: 1 }, |p| p as _) };
+            assert_eq!(cell.allocated_size(), size_of::<Inner<Robot>>());
+
+            // Doesn't need to borrow
+            let _guard = cell.borrow();
+            assert_eq!(cell.allocated_size(), size_of::<Inner<Robot>>());
+        }
+
+        #[test]
+        #[cfg(feature = "bytemuck")]
+        fn test_cast() {
+            let cell = ThinCell::<[u16]>::from(&[1, 2, 3, 4][..]);
+            let size = cell.allocated_size();
+
+            let cell = cell.cast::<u32>().unwrap();
+            assert_eq!(cell.borrow().len(), 2);
+            assert_eq!(cell.allocated_size(), size);
+
+            // Shared
+            assert!(cell.clone().cast::<u8>().is_err());
+            // Length not a multiple of 3
+            let cell = cell.cast::<[u8; 3]>().unwrap_err();
+            // Over-aligned
+            let cell = cell.cast::<u128>().unwrap_err();
+            let cell = cell.cast::<u8>().unwrap();
+            assert_eq!(cell.borrow().len(), 8);
+        }
+
+        #[test]
+        #[cfg(feature = "nightly")]
+        fn test_may_dangle() {
+            // `cell` is dropped after `value`, which it borrows
+            let cell;
+            let value = String::from("dangle");
+            cell = ThinCell::new(&value);
+            assert_eq!(*cell.borrow(), "dangle");
+        }
+
+        #[test]
+        fn test_unwind_safety() {
+            use std::panic::{AssertUnwindSafe, RefUnwindSafe, catch_unwind};
+
+            fn assert_ref_unwind_safe<T: RefUnwindSafe>(_: &T) {}
+
+            let cell = ThinCell::new(vec![1]);
+            let handle = cell.clone();
+            let result = catch_unwind(AssertUnwindSafe(move || {
+                let mut guard = handle.borrow();
+                assert_ref_unwind_safe(&guard);
+                guard.push(2);
+                panic!("half-updated");
+            }));
+            assert!(result.is_err());
+
+            // The borrow is released and the partial update is visible
+            assert_eq!(cell.count(), 1);
+            assert_eq!(*cell.borrow(), [1, 2]);
+        }
+
+        #[test]
+        fn test_borrow_mut_aliases() {
+            let cell = ThinCell::new(1);
+            *cell.borrow_mut() += 1;
+
+            let guard = cell.borrow_mut();
+            assert!(cell.try_borrow_mut().is_none());
+            assert!(cell.try_borrow().is_none());
+            drop(guard);
+            assert_eq!(*cell.try_borrow_mut().unwrap(), 2);
+        }
+
+        #[test]
+        fn test_ref_map() {
+            let cell = ThinCell::new((1, vec![2]));
+
+            let mut second = Ref::map(cell.borrow(), |v| &mut v.1);
+            second.push(3);
+            let mut first = Ref::map(second, |v| &mut v[0]);
+            *first += 10;
+            assert!(cell.try_borrow().is_none());
+            drop(first);
+            assert_eq!(*cell.borrow(), (1, vec![12, 3]));
+
+            // Panicking in `f` releases the borrow
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                Ref::map(cell.borrow(), |_| -> &mut i32 { panic!() })
+            }));
+            assert!(result.is_err());
+            assert!(cell.try_borrow().is_some());
+        }
+
+        #[test]
+        fn test_owned_ref_map() {
+            let cell = ThinCell::new((1, vec![2]));
+
+            let guard = cell.clone().borrow_owned();
+            assert_eq!(cell.count(), 2);
+            let mut second = OwnedRef::map(guard, |v| &mut v.1);
+            second.push(3);
+            assert!(cell.try_borrow().is_none());
+            assert!(OwnedRef::cell(&second).ptr_eq(&cell));
+
+            let second = OwnedRef::try_map(second, |v| v.get_mut(5)).unwrap_err();
+            let mut first = OwnedRef::try_map(second, |v| v.first_mut()).unwrap();
+            *first += 10;
+            drop(first);
+            assert_eq!(cell.count(), 1);
+            assert_eq!(*cell.borrow(), (1, vec![12, 3]));
+
+            let other = cell.clone();
+            let guard = other.borrow();
+            let cell = cell.try_borrow_owned().unwrap_err();
+            drop(guard);
+            assert_eq!(cell.try_borrow_owned().unwrap().0, 1);
+        }
+
+        #[test]
+        fn test_ref_on_drop() {
+            let cell = ThinCell::new(1);
+            let seen = Cell::new(0);
+
+            let mut value = Ref::on_drop(cell.borrow(), |v| {
+                // Still borrowed while the hook runs
+                assert!(cell.try_borrow().is_none());
+                seen.set(*v);
+            });
+            *value += 1;
+            assert_eq!(seen.get(), 0);
+            drop(value);
+            assert_eq!(seen.get(), 2);
+
+            // The borrow is released if the hook panics
+            let value = Ref::on_drop(cell.borrow(), |_| panic!());
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(value)));
+            assert!(result.is_err());
+            assert!(cell.try_borrow().is_some());
+        }
+
+        #[test]
+        fn test_projected_cell() {
+            let cell = ThinCell::new((String::from("a"), vec![1]));
+            let first = cell.clone().project(|v| &mut v.0);
+            let second = cell.clone().project(|v| &mut v.1);
+            assert_eq!(cell.count(), 3);
+
+            first.borrow().push('b');
+            second.borrow().push(2);
+            assert_eq!(*cell.borrow(), (String::from("ab"), vec![1, 2]));
+
+            // Borrowing a part borrows the whole cell
+            let guard = first.borrow();
+            assert!(second.try_borrow().is_none());
+            assert!(cell.try_borrow().is_none());
+            drop(guard);
+
+            let other = first.clone();
+            assert!(other.ptr_eq(&first));
+            assert_eq!(format!("{other:?}"), r#"ProjectedCell { value: "ab" }"#);
+            let guard = second.borrow();
+            assert_eq!(format!("{other:?}"), "ProjectedCell { value: <borrowed> }");
+            drop(guard);
+
+            drop((cell, first, other));
+            assert_eq!(second.count(), 1);
+            assert_eq!(*second.borrow(), [1, 2]);
+        }
+
+        #[test]
+        fn test_borrow_two() {
+            let a = ThinCell::new(1);
+            let b = ThinCell::new(String::from("x"));
+
+            let (mut x, mut y) = a.borrow_two(&b);
+            *x += 1;
+            y.push('y');
+            assert!(a.try_borrow().is_none());
+            assert!(b.try_borrow().is_none());
+            drop((x, y));
+
+            let (y, x) = b.borrow_two(&a);
+            assert_eq!((y.as_str(), *x), ("xy", 2));
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot borrow the same `ThinCell` twice")]
+        fn test_borrow_two_aliased() {
+            let a = ThinCell::new(1);
+            let _ = a.borrow_two(&a.clone());
+        }
+
+        #[test]
+        fn test_borrow_many() {
+            let cells: Vec<_> = (0..4).map(ThinCell::new).collect();
+
+            let guards = ThinCell::borrow_many([&cells[3], &cells[0], &cells[2]]);
+            assert_eq!(guards.each_ref().map(|g| **g), [3, 0, 2]);
+            assert!(cells[1].try_borrow().is_some());
+            assert!(cells[2].try_borrow().is_none());
+            drop(guards);
+
+            let [] = ThinCell::<i32>::borrow_many([]);
+            assert!(cells.iter().all(|cell| cell.try_borrow().is_some()));
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot borrow the same `ThinCell` twice")]
+        fn test_borrow_many_aliased() {
+            let a = ThinCell::new(1);
+            let b = ThinCell::new(2);
+            let _ = ThinCell::borrow_many([&a, &b, &a.clone()]);
+        }
+
+        #[test]
+        fn test_max_strong_count() {
+            let max = ThinCell::<[u8]>::MAX_STRONG_COUNT;
+            assert_eq!(max, thin_cell::raw::MAX_COUNT);
+
+            let max = if cfg!(feature = "abort-on-overflow") {
+                max * 2 + 1
+            } else {
+                max
+            };
+            #[cfg(target_pointer_width = "64")]
+            assert_eq!(max, (1 << 62) - 1);
+            #[cfg(target_pointer_width = "32")]
+            assert_eq!(max, 1_073_741_823);
+            #[cfg(target_pointer_width = "16")]
+            assert_eq!(max, 16_383);
+
+            // Every count up to the maximum, and the flags, fit in the state word
+            assert_eq!((max * thin_cell::raw::RC_UNIT) & thin_cell::raw::BORROW_MASK, 0);
+            assert!(max.checked_mul(thin_cell::raw::RC_UNIT).is_some());
+        }
+
+        #[test]
+        fn test_value_ops() {
+            let a = ThinCell::new(vec![1]);
+            let b = ThinCell::new(vec![2]);
+
+            assert_eq!(a.replace(vec![3]), [1]);
+            a.set(vec![4]);
+            a.swap(&b);
+            a.swap(&a.clone());
+            assert_eq!((a.take(), b.take()), (vec![2], vec![4]));
+            assert!(a.borrow().is_empty());
+
+            let flag = ThinCell::new(1u8);
+            assert_eq!(flag.compare_and_set(1, 2), Ok(1));
+            assert_eq!(flag.compare_and_set(1, 3), Err(2));
+            assert_eq!(*flag.borrow(), 2);
+        }
+
+        #[test]
+        fn test_try_value_ops() {
+            let a = ThinCell::new(String::from("a"));
+            let b = ThinCell::new(String::from("b"));
+
+            let guard = a.borrow();
+            let err = a.try_replace(String::from("x")).unwrap_err();
+            assert_eq!(err.to_string(), "Already borrowed");
+            assert_eq!(err.into_inner(), "x");
+            assert_eq!(a.try_set(String::from("y")).unwrap_err().into_inner(), "y");
+            assert!(a.try_take().is_err());
+            assert!(a.try_swap(&b).is_err());
+            assert!(b.try_swap(&a).is_err());
+            assert!(a.try_swap(&a).is_err());
+            // The failed swap didn't keep `b` borrowed
+            assert!(b.try_borrow().is_some());
+            drop(guard);
+
+            assert_eq!(a.try_replace(String::from("c")).unwrap(), "a");
+            a.try_set(String::from("d")).unwrap();
+            a.try_swap(&b).unwrap();
+            a.try_swap(&a).unwrap();
+            assert_eq!(a.try_take().unwrap(), "b");
+            assert_eq!(b.try_take().unwrap(), "d");
+        }
+
+        #[test]
+        fn test_set_drops_after_release() {
+            struct Probe(ThinCell<Option<Probe>>);
+
+            impl Drop for Probe {
+                fn drop(&mut self) {
+                    // Would panic if the old value was dropped while borrowed
+                    assert!(self.0.try_borrow().is_some());
+                }
+            }
+
+            let cell = ThinCell::new(None);
+            cell.set(Some(Probe(cell.clone())));
+            cell.set(None);
+        }
+
+        #[test]
+        fn test_option_helpers() {
+            let slot = ThinCell::new(None);
+            assert!(slot.is_none());
+            assert_eq!(slot.take_inner(), None::<String>);
+
+            let mut calls = 0;
+            for _ in 0..3 {
+                slot.get_or_insert_with(|| {
+                    calls += 1;
+                    String::new()
+                })
+                .push('a');
+            }
+            assert_eq!(calls, 1);
+            assert!(slot.is_some());
+
+            let guard = slot.get_or_insert_with(String::new);
+            assert!(slot.try_borrow().is_none());
+            drop(guard);
+
+            assert_eq!(slot.take_inner().as_deref(), Some("aaa"));
+            assert!(slot.is_none());
+        }
+
+        #[test]
+        fn test_once_thin_cell() {
+            assert_eq!(size_of::<OnceThinCell<String>>(), size_of::<usize>());
+
+            let cell = OnceThinCell::new();
+            let other = cell.clone();
+            assert_eq!(cell.count(), 2);
+            assert!(cell.ptr_eq(&other));
+            assert_eq!(format!("{cell:?}"), "OnceThinCell(<uninit>)");
+
+            assert_eq!(cell.set(vec![1]), Ok(()));
+            assert_eq!(cell.set(vec![2]), Err(vec![2]));
+            assert_eq!(other.get_or_init(|| unreachable!()), &[1]);
+
+            // Shared references stay valid across handles
+            let value = cell.get().unwrap();
+            let again = other.get().unwrap();
+            assert!(std::ptr::eq(value, again));
+            assert_eq!(format!("{other:?}"), "OnceThinCell([1])");
+
+            let cell = cell.try_unwrap().unwrap_err();
+            drop(other);
+            assert_eq!(cell.try_unwrap().unwrap(), Some(vec![1]));
+
+            let cell = OnceThinCell::<i32>::default();
+            assert_eq!(cell.try_unwrap().unwrap(), None);
+            assert_eq!(OnceThinCell::from(5).get(), Some(&5));
+        }
+
+        #[test]
+        fn test_lazy_thin_cell() {
+            let calls = std::rc::Rc::new(Cell::new(0));
+            let counter = calls.clone();
+            let lazy = LazyThinCell::new(move || {
+                counter.set(counter.get() + 1);
+                String::from("lazy")
+            });
+            let other = lazy.clone();
+            assert_eq!(lazy.count(), 2);
+            assert!(lazy.ptr_eq(&other));
+            assert!(format!("{lazy:?}").contains("<uninit>"));
+            assert_eq!(calls.get(), 0);
+
+            assert_eq!(LazyThinCell::force(&other), "lazy");
+            assert_eq!(lazy.len(), 4);
+            assert_eq!(calls.get(), 1);
+            assert!(format!("{lazy:?}").contains("\"lazy\""));
+
+            // The initializer is dropped once the value is built
+            assert_eq!(std::rc::Rc::strong_count(&calls), 1);
+
+            let lazy = LazyThinCell::<Vec<i32>>::default();
+            assert!(lazy.is_empty());
+        }
+
+        #[test]
+        fn test_from_slice_and_str() {
+            let cell = ThinCell::<[u64]>::from(&[1, 2, 3][..]);
+            assert_eq!(*cell.borrow(), [1, 2, 3]);
+            assert_eq!(cell.allocated_size(), size_of::<Inner<[u64; 3]>>());
+
+            let cell = ThinCell::<[u8]>::from(&[][..]);
+            assert!(cell.borrow().is_empty());
+
+            let cell = ThinCell::<[u16]>::from(&[7u16; 5][..]);
+            assert_eq!(cell.allocated_size(), size_of::<Inner<[u16; 5]>>());
+
+            let s = "hello".repeat(3);
+            let cell = ThinCell::<str>::from(s.as_str());
+            cell.borrow().make_ascii_uppercase();
+            assert_eq!(&*cell.borrow(), "HELLOHELLOHELLO");
+        }
+
+        #[test]
+        fn test_interner() {
+            let interner = Interner::default();
+            let a = interner.intern("a");
+            let b = interner.intern("b");
+            assert!(a.ptr_eq(&interner.intern(&String::from("a"))));
+            assert!(!a.ptr_eq(&b));
+            assert_eq!(interner.len(), 2);
+            assert_eq!(a.count(), 2);
+
+            // Borrowed or mutated strings are not matched
+            let guard = b.borrow();
+            assert!(!b.ptr_eq(&interner.intern("b")));
+            drop(guard);
+            b.borrow().make_ascii_uppercase();
+            let b2 = interner.intern("b");
+            assert!(!b.ptr_eq(&b2));
+            assert_eq!(&*b2.borrow(), "b");
+
+            drop(a);
+            interner.purge();
+            assert_eq!(interner.len(), 2);
+            drop((b, b2));
+            interner.purge();
+            assert!(interner.is_empty());
+            assert_eq!(format!("{interner:?}"), "Interner { len: 0 }");
+        }
+
+        #[test]
+        fn test_leak_registry() {
+            let registry = LeakRegistry::default();
+            let a = ThinCell::new(String::from("a"));
+            let b = ThinCell::new([1u8; 4]).unsize_slice();
+
+            let a1 = registry.leak(a.clone());
+            let a2 = registry.leak(a.clone());
+            let b1 = registry.leak(b.clone());
+            assert_eq!(registry.len(), 3);
+            assert_eq!((a.count(), b.count()), (3, 2));
+
+            // SAFETY: `a1` was leaked from a `ThinCell<String>`
+            let restored = unsafe { registry.from_raw::<String>(a1) }.unwrap();
+            assert_eq!(*restored.borrow(), "a");
+            drop(restored);
+            assert_eq!(registry.len(), 2);
+
+            assert_eq!(registry.reclaim_all(), 2);
+            assert!(registry.is_empty());
+            assert_eq!((a.count(), b.count()), (1, 1));
+            assert_eq!(format!("{registry:?}"), "LeakRegistry { len: 0 }");
+
+            // Late completions find nothing to restore
+            // SAFETY: No cell was leaked into the registry since
+            assert!(unsafe { registry.from_raw::<String>(a2) }.is_none());
+            assert!(unsafe { registry.from_raw::<[u8]>(b1) }.is_none());
+        }
+
+        #[test]
+        fn test_const_ptr_ops() {
+            const fn leak_both(a: ThinCell<u8>, b: ThinCell<u8>) -> [*mut (); 2] {
+                [a.leak(), b.leak()]
+            }
+
+            const fn ptr(cell: &ThinCell<u8>) -> *const () {
+                cell.as_ptr()
+            }
+
+            let cell = ThinCell::new(1u8);
+            let [a, b] = leak_both(cell.clone(), cell.clone());
+            assert_eq!(a.cast_const(), ptr(&cell));
+            assert_eq!(a, b);
+            assert_eq!(cell.count(), 3);
+            unsafe {
+                drop(ThinCell::<u8>::from_raw(a));
+                drop(ThinCell::<u8>::from_raw(b));
+            }
+        }
+
+        #[test]
+        fn test_concat() {
+            let parts: [&[String]; 3] = [&[String::from("a")], &[], &[String::from("b")]];
+            let cell = ThinCell::<[String]>::concat(&parts);
+            assert_eq!(&*cell.borrow(), ["a", "b"]);
+            assert_eq!(ThinCell::<[u8]>::concat(&[]).borrow().len(), 0);
+
+            let cell = ThinCell::<str>::concat(&["thin", "-", "cell"]);
+            assert_eq!(&*cell.borrow(), "thin-cell");
+        }
+
+        #[test]
+        fn test_thin_slice_view() {
+            let buf = ThinCell::new([0u8, 1, 2, 3, 4, 5]).unsize_slice();
+            let view = buf.slice(1..5);
+            assert_eq!((view.offset(), view.len()), (1, 4));
+            assert_eq!(&*view.borrow(), [1, 2, 3, 4]);
+
+            let sub = view.slice(2..=3);
+            assert_eq!((sub.offset(), sub.len()), (3, 2));
+            sub.borrow().fill(9);
+            assert_eq!(&*buf.borrow(), [0, 1, 2, 9, 9, 5]);
+            assert!(sub.cell().ptr_eq(&buf));
+            assert_eq!(buf.count(), 3);
+
+            let guard = buf.borrow();
+            assert!(view.try_borrow().is_none());
+            drop(guard);
+            assert!(view.slice(4..).is_empty());
+            assert_eq!(ThinSlice::from(buf).len(), 6);
+
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| view.slice(2..5)));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_thin_pool() {
+            static POOL: ThinPool<String, 2> = ThinPool::new();
+
+            let a = POOL.try_new(String::from("a")).unwrap();
+            let b = POOL.try_new(String::from("b")).unwrap();
+            assert_eq!(POOL.len(), 2);
+            assert_eq!(POOL.try_new(String::from("c")).unwrap_err(), "c");
+
+            let a2 = a.clone();
+            drop(a);
+            assert_eq!(POOL.len(), 2);
+            drop(a2);
+            assert_eq!(POOL.len(), 1);
+
+            let c = POOL.try_new(String::from("c")).unwrap();
+            assert_eq!(b.try_unwrap().unwrap(), "b");
+            assert_eq!(POOL.len(), 1);
+            c.borrow().push('!');
+            assert_eq!(&*c.borrow(), "c!");
+            drop(c);
+            assert!(POOL.is_empty());
+        }
+
+        #[test]
+        fn test_leak_tagged() {
+            let mask = thin_cell::raw::TAG_MASK;
+            let cell = ThinCell::new(String::from("tagged"));
+            let addr = cell.addr();
+
+            let ptr = cell.clone().leak_tagged(mask as u8);
+            assert_eq!(ptr.addr(), addr | mask);
+            let (restored, tag) = unsafe { ThinCell::<String>::from_raw_tagged(ptr) };
+            assert_eq!(tag as usize, mask);
+            assert!(restored.ptr_eq(&cell));
+            drop(restored);
+            assert_eq!(cell.count(), 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_leak_tagged_overflow() {
+            ThinCell::new(0u8).leak_tagged(thin_cell::raw::TAG_MASK as u8 + 1);
+        }
+
+        #[test]
+        fn test_raw() {
+            let layout = thin_cell::raw::layout::<u64>();
+            let alloc = std::ptr::NonNull::new(unsafe { std::alloc::alloc(layout) }).unwrap();
+            let cell = unsafe { raw::init(alloc.cast(), 7u64) };
+            let ptr = cell.as_ptr();
+            assert_eq!(ptr, alloc.as_ptr().cast_const().cast());
+
+            unsafe {
+                assert_eq!(*raw::data::<u64>(ptr), 7);
+                raw::increment(ptr);
+                assert_eq!(raw::load(ptr).count(), 2);
+
+                assert!(raw::try_borrow(ptr));
+                assert!(cell.try_borrow().is_none());
+                assert!(!raw::try_borrow(ptr));
+                raw::unborrow(ptr);
+                assert!(!raw::load(ptr).is_borrowed());
+
+                drop(ThinCell::<u64>::from_raw(ptr.cast_mut()));
+            }
+            assert_eq!(cell.count(), 1);
+            assert_eq!(cell.try_unwrap().unwrap(), 7);
+        }
+
+        #[test]
+        fn test_header_cell() {
+            let cell = HeaderCell::new(7u32, String::from("a"));
+            let other = cell.clone();
+            assert_eq!(cell.count(), 2);
+            assert!(cell.ptr_eq(&other));
+
+            let mut value = cell.borrow();
+            value.push('b');
+            assert_eq!(*other.header(), 7);
+            assert!(other.try_borrow().is_none());
+            assert_eq!(format!("{other:?}"), r#"HeaderCell { header: 7, value: <borrowed> }"#);
+            drop(value);
+
+            assert_eq!(*other.try_borrow().unwrap(), "ab");
+            let cell = cell.try_unwrap().unwrap_err();
+            drop(other);
+            assert_eq!(cell.try_unwrap().unwrap(), (7, String::from("ab")));
+        }
+
+        #[test]
+        fn test_erased_thin_cell() {
+            let cell = ErasedThinCell::new(vec![1u8]);
+            let other = cell.clone();
+            assert!(cell.is::<Vec<u8>>());
+            assert!(!cell.is::<Vec<i8>>());
+            assert_eq!(cell.type_id(), std::any::TypeId::of::<Vec<u8>>());
+
+            let mut value = cell.borrow::<Vec<u8>>().unwrap();
+            value.push(2);
+            assert!(other.try_borrow::<Vec<u8>>().is_none());
+            drop(value);
+            assert_eq!(*other.try_borrow::<Vec<u8>>().unwrap(), [1, 2]);
+
+            let other = other.downcast::<String>().unwrap_err();
+            let typed = other.downcast::<Vec<u8>>().unwrap();
+            assert_eq!(cell.count(), 2);
+            drop(cell);
+            assert_eq!(typed.try_unwrap().unwrap().1, [1, 2]);
+        }
+
+        #[test]
+        fn test_foreign_cell() {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+
+            struct Object(AtomicUsize);
+
+            impl thin_cell::ForeignRc for Object {
+                fn add_ref(&self) {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+
+                fn release(&self) {
+                    self.0.fetch_sub(1, Ordering::Relaxed);
+                }
+            }
+
+            let cell = ForeignCell::new(Object(AtomicUsize::new(1)), vec![1]);
+            let other = cell.clone();
+            assert_eq!(cell.header().0.load(Ordering::Relaxed), 2);
+            assert_eq!(cell.count(), 2);
+
+            other.borrow().push(2);
+            drop(other);
+            assert_eq!(cell.header().0.load(Ordering::Relaxed), 1);
+            assert_eq!(*cell.borrow(), [1, 2]);
+        }
+
+        #[cfg(not(feature = "abort-on-drop-panic"))]
+        #[test]
+        fn test_panicking_drop() {
+            struct PanicOnDrop {
+                _value: std::sync::Arc<()>,
+            }
+
+            impl Drop for PanicOnDrop {
+                fn drop(&mut self) {
+                    panic!("drop");
+                }
+            }
+
+            let value = std::sync::Arc::new(());
+            let cell = ThinCell::new(PanicOnDrop {
+                _value: value.clone(),
+            });
+            let other = cell.clone();
+            drop(cell);
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(other)));
+            assert!(result.is_err());
+            // The fields are still dropped while unwinding
+            assert_eq!(std::sync::Arc::strong_count(&value), 1);
+        }
+
+        #[test]
+        fn test_thin_task() {
+            use std::{
+                future::poll_fn,
+                task::{Poll, Waker},
+            };
+
+            let mut pending = 2;
+            let task = ThinTask::new(poll_fn(move |cx| {
+                if pending == 0 {
+                    return Poll::Ready(String::from("done"));
+                }
+                pending -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }));
+            let other = task.clone();
+            assert!(task.ptr_eq(&other));
+
+            assert_eq!(task.poll(Waker::noop()), Poll::Pending);
+            assert_eq!(other.poll(Waker::noop()), Poll::Pending);
+            assert!(!task.is_finished());
+            assert_eq!(task.take_output(), None);
+
+            assert_eq!(task.poll(Waker::noop()), Poll::Ready(()));
+            assert!(other.is_finished());
+            assert_eq!(other.poll(Waker::noop()), Poll::Ready(()));
+            assert_eq!(other.take_output().as_deref(), Some("done"));
+            assert_eq!(task.take_output(), None);
+
+            // Unfinished futures are dropped with the last handle
+            let value = std::sync::Arc::new(());
+            let captured = value.clone();
+            drop(ThinTask::new(async move { drop(captured) }));
+            assert_eq!(std::sync::Arc::strong_count(&value), 1);
+        }
+
+        #[test]
+        fn test_cancel_token() {
+            use std::{
+                future::Future,
+                pin::pin,
+                sync::{
+                    Arc,
+                    atomic::{AtomicUsize, Ordering},
+                },
+                task::{Context, Poll, Wake, Waker},
+            };
+
+            struct Counter(AtomicUsize);
+
+            impl Wake for Counter {
+                fn wake(self: Arc<Self>) {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let counter = Arc::new(Counter(AtomicUsize::new(0)));
+            let waker = Waker::from(counter.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            let token = ThinCancelToken::default();
+            let op = token.clone();
+            assert!(token.ptr_eq(&op));
+            assert_eq!(token.count(), 2);
+
+            let mut cancelled = pin!(op.cancelled());
+            assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+            // Polling again with the same waker doesn't register it twice
+            assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+            assert!(!op.is_cancelled());
+
+            token.cancel();
+            token.cancel();
+            assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+            assert!(op.is_cancelled());
+            assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Ready(()));
+            assert_eq!(format!("{op:?}"), "ThinCancelToken { cancelled: true }");
+        }
+
+        #[test]
+        fn test_io() {
+            use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+            let cell = ThinCell::new(Cursor::new(Vec::new()));
+            (&cell).write_all(b"hello").unwrap();
+            assert_eq!((&cell).seek(SeekFrom::Start(1)).unwrap(), 1);
+
+            let mut buf = String::new();
+            (&cell).read_to_string(&mut buf).unwrap();
+            assert_eq!(buf, "ello");
+
+            let guard = cell.borrow();
+            let err = (&cell).write(b"!").unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+            assert_eq!(err.to_string(), "Already borrowed");
+            drop(guard);
+
+            (&cell).rewind().unwrap();
+            let mut out = Vec::new();
+            std::io::copy(&mut &cell, &mut out).unwrap();
+            assert_eq!(out, b"hello");
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn test_as_fd() {
+            use std::os::fd::{AsFd, AsRawFd};
+
+            let file = std::fs::File::open("Cargo.toml").unwrap();
+            let fd = file.as_raw_fd();
+            let cell = ThinCell::new(file);
+            assert_eq!(cell.as_raw_fd(), fd);
+
+            let guard = cell.borrow();
+            assert_eq!(guard.as_fd().as_raw_fd(), fd);
+        }
+
+        #[test]
+        fn test_thin_cow() {
+            assert_eq!(size_of::<ThinCow<str>>(), size_of::<&str>());
+
+            let input = String::from("key=value");
+            let mut cow = ThinCow::Borrowed(&input[..3]);
+            let shared = cow.clone();
+            assert!(cow.is_borrowed());
+            assert_eq!(cow.with(str::len), 3);
+
+            cow.to_mut().make_ascii_uppercase();
+            assert!(cow.is_owned());
+            assert_eq!(cow.to_string(), "KEY");
+            assert_eq!(shared.to_string(), "key");
+
+            let cell = cow.clone().into_owned();
+            assert!(matches!(&cow, ThinCow::Owned(owned) if owned.ptr_eq(&cell)));
+            assert_eq!(&*shared.into_owned().borrow(), "key");
+        }
+
+        #[test]
+        fn test_thin_error() {
+            use std::{error::Error, fmt};
+
+            #[derive(Debug)]
+            struct Outer(fmt::Error);
+
+            impl fmt::Display for Outer {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("outer")
+                }
+            }
+
+            impl Error for Outer {
+                fn source(&self) -> Option<&(dyn Error + 'static)> {
+                    Some(&self.0)
+                }
+            }
+
+            let err = ThinError::new(Outer(fmt::Error));
+            let other = err.clone();
+            assert!(err.ptr_eq(&other));
+            assert_eq!(other.to_string(), "outer");
+            assert_eq!(format!("{other:?}"), "Outer(Error)");
+            assert!(other.source().unwrap().is::<fmt::Error>());
+            assert!(err.downcast_ref::<Outer>().is_some());
+            assert!(err.downcast_ref::<fmt::Error>().is_none());
+
+            fn fails() -> Result<(), Box<dyn Error>> {
+                Err(ThinError::new(fmt::Error))?
+            }
+            assert!(fails().unwrap_err().is::<ThinError>());
+        }
+
+        #[test]
+        fn test_any_map() {
+            let mut map = ThinAnyMap::new();
+            assert!(map.insert(1u32).is_none());
+            assert!(map.insert(String::from("a")).is_none());
+            assert_eq!(map.len(), 2);
+
+            // Handles share the stored cell, even while it is borrowed
+            let cell = map.get::<u32>().unwrap();
+            let guard = cell.borrow();
+            assert!(cell.ptr_eq(&map.get::<u32>().unwrap()));
+            drop(guard);
+
+            let old = map.insert(2u32).unwrap();
+            assert!(old.ptr_eq(&cell));
+            assert_eq!(*map.get::<u32>().unwrap().borrow(), 2);
+
+            assert!(map.insert_cell(cell.clone()).is_some());
+            assert!(map.get::<u32>().unwrap().ptr_eq(&cell));
+            assert!(map.get::<i32>().is_none());
+
+            let s = map.remove::<String>().unwrap();
+            assert_eq!(&*s.borrow(), "a");
+            assert!(!map.contains::<String>());
+            assert_eq!(format!("{map:?}"), "ThinAnyMap { len: 1 }");
+            map.clear();
+            assert!(map.is_empty());
+        }
+
+        #[test]
+        fn test_map_value() {
+            let cell = ThinCell::new(5u32);
+            let ptr = cell.as_ptr();
+            let cell = cell.map_value(|v| v as i32 * -2);
+            assert_eq!(cell.as_ptr(), ptr);
+            assert_eq!(*cell.borrow(), -10);
+
+            // Different layout reallocates
+            let cell = cell.map_value(|v| vec![v; 3]);
+            assert_eq!(*cell.borrow(), [-10; 3]);
+            let cell = cell.map_value(|v| v.len() as u8);
+            assert_eq!(*cell.borrow(), 3);
+
+            // External cells keep their slot
+            let mut cells = ThinCell::new_many([String::from("a"), String::from("b")]);
+            let cell = cells.pop().unwrap();
+            let ptr = cell.as_ptr();
+            let cell = cell.map_value(|s| s.into_bytes());
+            assert_eq!(cell.as_ptr(), ptr);
+            assert_eq!(*cell.borrow(), b"b");
+            drop(cells);
+            drop(cell);
+
+            // Panicking frees the allocation
+            struct DropFlag(std::rc::Rc<Cell<usize>>);
+
+            impl Drop for DropFlag {
+                fn drop(&mut self) {
+                    self.0.set(self.0.get() + 1);
+                }
+            }
+
+            let flag = std::rc::Rc::new(Cell::new(0));
+            let cell = ThinCell::new(DropFlag(flag.clone()));
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                cell.map_value(|v| -> DropFlag {
+                    drop(v);
+                    panic!()
+                })
+            }));
+            assert!(result.is_err());
+            assert_eq!(flag.get(), 1);
+        }
+
+        #[test]
+        fn test_recycle() {
+            use std::rc::Rc;
+
+            let flag = Rc::new(Cell::new(0));
+            let cell = ThinCell::new(flag.clone());
+            let ptr = cell.as_ptr();
+
+            let other = cell.clone();
+            let cell = cell.recycle(Rc::new(Cell::new(1))).unwrap_err();
+            drop(other);
+
+            let cell = cell.recycle(Rc::new(Cell::new(2))).unwrap();
+            assert_eq!(Rc::strong_count(&flag), 1);
+            assert_eq!(cell.as_ptr(), ptr);
+            assert_eq!(cell.count(), 1);
+            assert_eq!(cell.borrow().get(), 2);
+
+            let mut cells = ThinCell::new_many([1, 2]);
+            let cell = cells.pop().unwrap().recycle(3).unwrap();
+            assert_eq!(cell.count(), 1);
+            drop(cells);
+            assert_eq!(cell.try_unwrap().unwrap(), 3);
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot map shared `ThinCell`")]
+        fn test_map_value_shared() {
+            let cell = ThinCell::new(1);
+            let _other = cell.clone();
+            cell.map_value(|v| v + 1);
+        }
+
+        #[test]
+        fn test_addr() {
+            let cell = ThinCell::new([1u8; 4]);
+            let addr = cell.addr();
+            assert_eq!(addr, cell.as_ptr().addr());
+            assert_eq!(addr % align_of::<usize>(), 0);
+
+            let cell = cell.unsize_slice();
+            assert_eq!(cell.addr(), addr);
+            assert_ne!(cell.addr(), ThinCell::new(0).addr());
+        }
+
+        #[test]
+        fn test_ptr_eq_with() {
+            let cell = ThinCell::new([1, 2]);
+            let slice = cell.unsize_slice();
+            let robot = unsafe { ThinCell::<dyn Greeter>::new_unsize(Robot { id: 1 }, |p| p as _) };
+            assert!(slice.ptr_eq_with(&slice.clone()));
+            assert!(!slice.ptr_eq_with(&robot));
+
+            let any = unsafe { ThinCell::<dyn std::any::Any>::new_unsize(5u8, |p| p as _) };
+            let concrete = any.clone().downcast::<u8>().unwrap();
+            assert!(any.ptr_eq_with(&concrete));
+            assert!(concrete.ptr_eq_with(&any));
+        }
+
+        #[test]
+        fn test_try_borrow_unguarded() {
+            let cell = ThinCell::new(vec![1]);
+            let other = cell.clone();
+
+            unsafe {
+                assert_eq!(cell.try_borrow_unguarded().unwrap(), &[1]);
+                let guard = other.borrow();
+                assert!(cell.try_borrow_unguarded().is_none());
+                drop(guard);
+
+                // Doesn't hold the borrow flag, so references can coexist
+                let first = cell.try_borrow_unguarded().unwrap();
+                let second = other.try_borrow_unguarded().unwrap();
+                assert!(std::ptr::eq(first, second));
+            }
+        }
+
+        #[test]
+        fn test_alternate_debug() {
+            let cell = ThinCell::new(vec![1]);
+            let other = cell.clone();
+
+            let dump = format!("{cell:#?}");
+            assert!(dump.starts_with("ThinCell {\n"));
+            assert!(dump.contains(&format!("addr: {:#x},", cell.addr())));
+            assert!(dump.contains("type: alloc::vec::Vec<i32>,"));
+            assert!(dump.contains("count: 2,"));
+            assert!(dump.contains("borrowed: false,"));
+            assert!(dump.contains("value: [\n"));
+
+            let guard = other.borrow();
+            let dump = format!("{cell:#?}");
+            assert!(dump.contains("borrowed: true,"));
+            assert!(dump.contains("value: <borrowed>,"));
+            assert!(format!("{cell:?}").contains("value: <borrowed>,"));
+            drop(guard);
+
+            // The regular form is unchanged
+            assert!(!format!("{cell:?}").contains("addr"));
+        }
+
+        #[test]
+        fn test_value_eq() {
+            let a = ThinCell::new(String::from("a"));
+            let b = ThinCell::new(String::from("a"));
+            let c = ThinCell::new(String::from("c"));
+            assert_eq!(a.value_eq(&b), Some(true));
+            assert_eq!(a.value_eq(&c), Some(false));
+
+            let guard = b.borrow();
+            assert_eq!(a.value_eq(&b), None);
+            assert_eq!(b.value_eq(&a), None);
+            assert_eq!(b.value_eq(&b.clone()), None);
+            drop(guard);
+            assert_eq!(b.value_eq(&b.clone()), Some(true));
+
+            // A cell is compared with itself through `PartialEq`, which may be false
+            let nan = ThinCell::new(f64::NAN);
+            assert_eq!(nan.value_eq(&nan), Some(false));
+
+            // Failed comparisons leave no borrow behind
+            assert!(a.try_borrow().is_some());
+
+            let x: ThinCell<[i32]> = ThinCell::new([1, 2]).unsize_slice();
+            let y: ThinCell<[i32]> = ThinCell::new([1, 2]).unsize_slice();
+            assert_eq!(x.value_eq(&y), Some(true));
+        }
+
+        #[test]
+        fn test_deep_clone() {
+            let cell = ThinCell::new(String::from("a"));
+            let _other = cell.clone();
+            let fork = cell.deep_clone();
+            assert_eq!(fork.count(), 1);
+            fork.borrow().push('b');
+            assert_eq!(*cell.borrow(), "a");
+
+            let slice: ThinCell<[String]> = ThinCell::new([String::from("x")]).unsize_slice();
+            let fork = slice.deep_clone();
+            fork.borrow()[0].push('y');
+            assert_eq!(slice.borrow()[0], "x");
+            assert_eq!(fork.allocated_size(), slice.allocated_size());
+
+            let s = ThinCell::<str>::from("str");
+            let fork = s.deep_clone();
+            fork.borrow().make_ascii_uppercase();
+            assert_eq!((&*s.borrow(), &*fork.borrow()), ("str", "STR"));
+        }
+
+        #[test]
+        fn test_from_slice_clone_panic() {
+            struct Bomb(std::rc::Rc<Cell<usize>>, bool);
+
+            impl Clone for Bomb {
+                fn clone(&self) -> Self {
+                    assert!(!self.1, "boom");
+                    Bomb(self.0.clone(), false)
+                }
+            }
+
+            let live = std::rc::Rc::new(Cell::new(0));
+            let items = [
+                Bomb(live.clone(), false),
+                Bomb(live.clone(), false),
+                Bomb(live.clone(), true),
+            ];
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                ThinCell::<[Bomb]>::from(&items[..])
+            }));
+            assert!(result.is_err());
+            // The two clones made before the panic were dropped
+            assert_eq!(std::rc::Rc::strong_count(&live), 4);
+        }
+
+        #[test]
+        fn test_from_rc_and_arc() {
+            use std::{rc::Rc, sync::Arc};
+
+            let rc = Rc::new(String::from("rc"));
+            let other = rc.clone();
+            let rc = ThinCell::try_from_rc(rc).unwrap_err();
+            let cloned = ThinCell::from(other);
+            cloned.borrow().push('!');
+            assert_eq!(*rc, "rc");
+
+            let weak = Rc::downgrade(&rc);
+            let moved = ThinCell::try_from_rc(rc).unwrap();
+            assert_eq!(*moved.borrow(), "rc");
+            assert!(weak.upgrade().is_none());
+
+            let arc = Arc::new(vec![1]);
+            let other = arc.clone();
+            let arc = ThinCell::try_from_arc(arc).unwrap_err();
+            assert_eq!(*ThinCell::from(other).borrow(), [1]);
+            let moved = ThinCell::from(arc);
+            assert_eq!(*moved.borrow(), [1]);
+
+            // Not `Clone`
+            struct Unique(u8);
+            let cell = ThinCell::try_from_rc(Rc::new(Unique(3))).ok().unwrap();
+            assert_eq!(cell.borrow().0, 3);
+        }
+
+        #[test]
+        fn test_into_box() {
+            let cell = ThinCell::new(String::from("boxed"));
+            let other = cell.clone();
+            let cell = cell.into_box().unwrap_err();
+            drop(other);
+
+            let boxed: Box<String> = cell.into_box().unwrap();
+            assert_eq!(*boxed, "boxed");
+        }
+
+        #[test]
+        fn test_into_vec_and_string() {
+            #[derive(Debug)]
+            struct DropFlag<'a>(&'a Cell<usize>);
+
+            impl<'a> Drop for DropFlag<'a> {
+                fn drop(&mut self) {
+                    self.0.update(|x| x + 1);
+                }
+            }
+
+            let flag = Cell::new(0);
+            let cell = ThinCell::new([DropFlag(&flag), DropFlag(&flag)]).unsize_slice();
+            let other = cell.clone();
+            let cell = cell.into_vec().unwrap_err();
+            drop(other);
+
+            let vec = cell.into_vec().unwrap();
+            assert_eq!(vec.len(), 2);
+            assert_eq!(flag.get(), 0);
+            drop(vec);
+            assert_eq!(flag.get(), 2);
+
+            let mut cells = ThinCell::new_many([[1u16, 2, 3], [4, 5, 6]])
+                .into_iter()
+                .map(ThinCell::unsize_slice);
+            let first = cells.next().unwrap();
+            let second = cells.next().unwrap();
+            assert_eq!(first.into_vec().unwrap(), [1, 2, 3]);
+            assert_eq!(second.into_vec().unwrap(), [4, 5, 6]);
+
+            let cell = ThinCell::<str>::from("thin");
+            let borrowed = cell.clone();
+            let guard = borrowed.borrow();
+            let cell = cell.into_string().unwrap_err();
+            drop(guard);
+            drop(borrowed);
+            assert_eq!(cell.into_string().unwrap(), "thin");
+        }
     };
 }
 
@@ -0,0 +1,110 @@
+#![cfg(feature = "debug")]
+
+use thin_cell::{debug, sync, unsync};
+
+#[test]
+fn test_live_records_type_and_location() {
+    let line = line!() + 1;
+    let cell = unsync::ThinCell::new(42u32);
+
+    let live = debug::live();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].addr, cell.as_ptr() as usize);
+    assert_eq!(live[0].type_name, "u32");
+    assert_eq!(live[0].location.file(), file!());
+    assert_eq!(live[0].location.line(), line);
+
+    let other = cell.clone();
+    assert_eq!(debug::live().len(), 1);
+
+    drop(cell);
+    drop(other);
+    debug::assert_no_leaks();
+}
+
+#[test]
+fn test_leak_and_from_raw() {
+    let ptr = sync::ThinCell::new([1u8, 2, 3]).unsize_slice().leak();
+    assert_eq!(debug::live().len(), 1);
+    assert_eq!(debug::live()[0].type_name, "[u8; 3]");
+
+    drop(unsafe { sync::ThinCell::<[u8]>::from_raw(ptr) });
+    debug::assert_no_leaks();
+}
+
+#[test]
+fn test_unwrap_and_external_cells() {
+    let cells = unsync::ThinCell::new_many([1, 2, 3]);
+    let mut slab = sync::ThinSlab::new();
+    let key = slab.insert(String::new());
+    assert_eq!(debug::live().len(), 4);
+
+    assert_eq!(unsync::ThinCell::new(0).try_unwrap().ok(), Some(0));
+    drop(cells);
+    assert_eq!(debug::live().len(), 1);
+
+    slab.remove(key).unwrap().try_unwrap().unwrap();
+    debug::assert_no_leaks();
+}
+
+#[test]
+#[should_panic(expected = "1 `ThinCell` allocation(s) leaked")]
+fn test_assert_no_leaks() {
+    std::mem::forget(unsync::ThinCell::new(1));
+
+    debug::assert_no_leaks();
+}
+
+struct Node {
+    edges: Vec<unsync::ThinCell<Node>>,
+}
+
+impl debug::Trace for Node {
+    fn trace(&self, tracer: &mut debug::Tracer) {
+        self.edges.trace(tracer);
+    }
+}
+
+fn node() -> unsync::ThinCell<Node> {
+    unsync::ThinCell::new(Node { edges: Vec::new() })
+}
+
+#[test]
+fn test_find_cycles() {
+    use debug::find_cycles;
+
+    // a -> b -> c -> b, a -> d -> d
+    let (a, b, c, d) = (node(), node(), node(), node());
+    a.borrow().edges.extend([b.clone(), d.clone()]);
+    b.borrow().edges.push(c.clone());
+    c.borrow().edges.push(b.clone());
+    d.borrow().edges.push(d.clone());
+
+    let mut cycles = find_cycles(&a);
+    cycles.sort_by_key(|cycle| cycle.cells.len());
+    assert_eq!(cycles.len(), 2);
+    assert_eq!(cycles[0].cells.len(), 1);
+    assert_eq!(cycles[0].cells[0].addr, d.as_ptr() as usize);
+    assert!(cycles[0].cells[0].type_name.ends_with("::Node"));
+    // `d` held by `a` and the test
+    assert_eq!(cycles[0].external, 2);
+    assert_eq!(cycles[1].cells.len(), 2);
+    // `b` held by `a` and the test, `c` by the test
+    assert_eq!(cycles[1].external, 3);
+    assert!(
+        cycles[1]
+            .to_string()
+            .starts_with("cycle of 2 cell(s) with 3 external handle(s):")
+    );
+
+    // Borrowed cells are not traced further
+    let guard = b.borrow();
+    assert_eq!(find_cycles(&a).len(), 1);
+    drop(guard);
+
+    for cell in [&a, &b, &c, &d] {
+        cell.borrow().edges.clear();
+    }
+    assert!(find_cycles(&[a, b, c, d]).is_empty());
+    debug::assert_no_leaks();
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "fail-alloc")]
+
+use std::{cell::Cell, rc::Rc};
+
+use thin_cell::{AllocError, fail_alloc, sync, unsync};
+
+#[test]
+fn test_fail_alloc() {
+    assert_eq!(fail_alloc::pending(), 0);
+
+    fail_alloc::fail_next(2);
+    assert_eq!(unsync::ThinCell::try_new(1).err(), Some(AllocError));
+    assert_eq!(fail_alloc::pending(), 1);
+
+    // Infallible constructors don't consume failures
+    let cell = unsync::ThinCell::new(2);
+    assert_eq!(fail_alloc::pending(), 1);
+
+    assert_eq!(sync::ThinCell::try_new(3).err(), Some(AllocError));
+    assert_eq!(fail_alloc::pending(), 0);
+
+    let other = unsync::ThinCell::try_new(4).unwrap();
+    assert_eq!(*cell.borrow() + *other.borrow(), 6);
+}
+
+#[test]
+fn test_fail_alloc_drops_value() {
+    struct DropFlag(Rc<Cell<usize>>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.update(|x| x + 1);
+        }
+    }
+
+    let flag = Rc::new(Cell::new(0));
+
+    fail_alloc::fail_next(1);
+    assert!(unsync::ThinCell::try_new(DropFlag(flag.clone())).is_err());
+    assert_eq!(flag.get(), 1);
+}
+
+#[test]
+fn test_fail_alloc_per_thread() {
+    fail_alloc::fail_next(1);
+
+    std::thread::spawn(|| {
+        assert_eq!(fail_alloc::pending(), 0);
+        assert!(sync::ThinCell::try_new(1).is_ok());
+    })
+    .join()
+    .unwrap();
+
+    assert!(sync::ThinCell::try_new(1).is_err());
+}
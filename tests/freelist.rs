@@ -0,0 +1,70 @@
+#![cfg(feature = "freelist")]
+
+use thin_cell::{sync, unsync};
+
+#[test]
+fn test_freelist_reuse() {
+    let cell = unsync::ThinCell::new([1u64; 4]);
+    let ptr = cell.as_ptr();
+    drop(cell);
+
+    // Same layout, different type and flavour
+    let cell = sync::ThinCell::new([2u32; 8]);
+    assert_eq!(cell.as_ptr(), ptr);
+    assert_eq!(cell.try_unwrap().unwrap(), [2; 8]);
+
+    let cell = unsync::ThinCell::new([3u64; 4]);
+    assert_eq!(cell.as_ptr(), ptr);
+    assert_eq!(*cell.borrow(), [3; 4]);
+}
+
+#[test]
+fn test_freelist_new_with() {
+    let cell = unsync::ThinCell::new([1u64; 3]);
+    let ptr = cell.as_ptr();
+    drop(cell);
+
+    // SAFETY: `f` initializes the value, or fails
+    let res = unsafe { unsync::ThinCell::<[u64; 3]>::try_new_with(|_| Err(())) };
+    assert!(res.is_err());
+
+    // SAFETY: `f` initializes the value
+    let cell = unsafe { unsync::ThinCell::<[u64; 3]>::new_with(|data| _ = data.write([2; 3])) };
+    assert_eq!(cell.as_ptr(), ptr);
+    assert_eq!(*cell.borrow(), [2; 3]);
+}
+
+#[test]
+fn test_freelist_across_threads() {
+    let cell = sync::ThinCell::new(String::from("moved"));
+    let cell = std::thread::spawn(move || {
+        assert_eq!(&*cell.borrow(), "moved");
+        sync::ThinCell::new(String::from("back"))
+    })
+    .join()
+    .unwrap();
+    assert_eq!(&*cell.borrow(), "back");
+}
+
+#[cfg(not(feature = "abort-on-drop-panic"))]
+#[test]
+fn test_freelist_panicking_drop() {
+    struct PanicOnDrop {
+        _padding: [u64; 5],
+    }
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            panic!("drop");
+        }
+    }
+
+    let cell = unsync::ThinCell::new(PanicOnDrop { _padding: [0; 5] });
+    let ptr = cell.as_ptr();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(cell)));
+    assert!(result.is_err());
+
+    // The allocation was released while unwinding
+    let cell = unsync::ThinCell::new([1u64; 5]);
+    assert_eq!(cell.as_ptr(), ptr);
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "futures-io")]
+
+use std::{
+    io::ErrorKind,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use futures_io::{AsyncRead, AsyncWrite};
+use thin_cell::{sync, unsync};
+
+fn ready<R>(poll: Poll<R>) -> R {
+    match poll {
+        Poll::Ready(r) => r,
+        Poll::Pending => panic!("unexpected pending"),
+    }
+}
+
+#[test]
+fn test_async_write_read() {
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let cell = unsync::ThinCell::new(Vec::new());
+    let n = ready(Pin::new(&mut &cell).poll_write(&mut cx, b"hello")).unwrap();
+    assert_eq!(n, 5);
+    ready(Pin::new(&mut cell.clone()).poll_flush(&mut cx)).unwrap();
+    assert_eq!(&*cell.borrow(), b"hello");
+
+    let cell = sync::ThinCell::new(&b"hello"[..]);
+    let mut buf = [0; 3];
+    let n = ready(Pin::new(&mut &cell).poll_read(&mut cx, &mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"hel");
+    let n = ready(Pin::new(&mut cell.clone()).poll_read(&mut cx, &mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"lo");
+}
+
+#[test]
+fn test_async_borrowed() {
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let cell = unsync::ThinCell::new(Vec::new());
+    let guard = cell.borrow();
+    let err = ready(Pin::new(&mut &cell).poll_write(&mut cx, b"!")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+    drop(guard);
+
+    ready(Pin::new(&mut &cell).poll_close(&mut cx)).unwrap();
+}
@@ -0,0 +1,119 @@
+//! Model tests of the memory orderings of `sync::ThinCell`, exploring every
+//! interleaving of a few threads with loom.
+//!
+//! Run in release mode, as exploring the interleavings is slow:
+//! `RUSTFLAGS="--cfg thin_cell_loom" cargo test --release --test loom`.
+
+#![cfg(thin_cell_loom)]
+
+use loom::{cell::UnsafeCell, thread};
+use thin_cell::sync::ThinCell;
+
+/// A value whose accesses are checked by loom, so that an access not ordered
+/// after the previous one through the state of the cell is reported.
+struct Probe(UnsafeCell<usize>);
+
+// SAFETY: The value is only accessed through borrows of the cell, or by its
+// last owner when dropped.
+unsafe impl Sync for Probe {}
+
+impl Probe {
+    fn new() -> Self {
+        Probe(UnsafeCell::new(0))
+    }
+
+    fn incr(&self) {
+        // SAFETY: Checked by loom
+        self.0.with_mut(|value| unsafe { *value += 1 });
+    }
+
+    fn get(&self) -> usize {
+        // SAFETY: Checked by loom
+        self.0.with(|value| unsafe { *value })
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        // Must be ordered after the writes of every other owner
+        self.get();
+    }
+}
+
+#[test]
+fn test_loom_clone_drop() {
+    loom::model(|| {
+        let cell = ThinCell::new(Probe::new());
+
+        let threads = (0..2)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    let other = cell.clone();
+                    other.borrow().incr();
+                    drop(other);
+                })
+            })
+            .collect::<Vec<_>>();
+        // The last owner may be any of the three threads
+        drop(cell);
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn test_loom_borrow() {
+    loom::model(|| {
+        let cell = ThinCell::new(Probe::new());
+
+        let other = cell.clone();
+        let thread = thread::spawn(move || other.borrow().incr());
+        cell.borrow().incr();
+        thread.join().unwrap();
+
+        assert_eq!(cell.borrow().get(), 2);
+    });
+}
+
+#[test]
+fn test_loom_try_borrow() {
+    loom::model(|| {
+        let cell = ThinCell::new(Probe::new());
+
+        let other = cell.clone();
+        let thread = thread::spawn(move || other.try_borrow().map(|value| value.incr()).is_some());
+        let borrowed = cell.try_borrow().map(|value| value.incr()).is_some();
+        let other_borrowed = thread.join().unwrap();
+
+        // At least one of them got the borrow, and no write was lost
+        assert!(borrowed || other_borrowed);
+        assert_eq!(
+            cell.borrow().get(),
+            borrowed as usize + other_borrowed as usize
+        );
+    });
+}
+
+#[test]
+fn test_loom_try_unwrap() {
+    loom::model(|| {
+        let cell = ThinCell::new(Probe::new());
+
+        let other = cell.clone();
+        let thread = thread::spawn(move || {
+            other.borrow().incr();
+            other.try_unwrap().map(|value| value.get()).ok()
+        });
+        let unwrapped = cell.try_unwrap().map(|value| value.get()).ok();
+        let other_unwrapped = thread.join().unwrap();
+
+        // At most one owner takes the value, after the write of the other one
+        assert!(unwrapped.is_none() || other_unwrapped.is_none());
+        if let Some(value) = unwrapped {
+            assert_eq!(value, 1);
+        }
+    });
+}
@@ -0,0 +1,199 @@
+//! Drives cells and a reference model (`Rc<RefCell<T>>` with an explicit borrow
+//! flag) through the same pseudo-random operation sequences, and compares what
+//! can be observed after every step.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+/// Deterministic xorshift generator, so failures reproduce from the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Any non-zero state works, mix the seed so neighbours diverge quickly
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Model of a cell: its value, and whether it is borrowed. Owners are counted
+/// by the `Rc`.
+struct Model {
+    value: RefCell<u32>,
+    borrowed: Cell<bool>,
+}
+
+const SEEDS: u64 = 64;
+const STEPS: usize = 500;
+
+macro_rules! model_test {
+    ($flavour:ident) => {
+        mod $flavour {
+            use std::any::Any;
+
+            use thin_cell::$flavour::{OwnedRef, ThinCell};
+
+            use super::*;
+
+            struct Harness {
+                handles: Vec<(ThinCell<u32>, Rc<Model>)>,
+                guards: Vec<(OwnedRef<u32>, Rc<Model>)>,
+            }
+
+            impl Harness {
+                fn check(&self, seed: u64, step: usize) {
+                    for (cell, model) in &self.handles {
+                        // Handles and guards each hold a cell and an `Rc`
+                        assert_eq!(
+                            cell.count(),
+                            Rc::strong_count(model),
+                            "count, seed {seed} step {step}"
+                        );
+                        match cell.try_borrow() {
+                            Some(value) => {
+                                assert!(!model.borrowed.get(), "seed {seed} step {step}");
+                                assert_eq!(*value, *model.value.borrow());
+                            }
+                            None => assert!(model.borrowed.get(), "seed {seed} step {step}"),
+                        }
+                    }
+                    for (guard, model) in &self.guards {
+                        assert_eq!(**guard, *model.value.borrow(), "seed {seed} step {step}");
+                    }
+                    for (a, model_a) in &self.handles {
+                        for (b, model_b) in &self.handles {
+                            assert_eq!(a.ptr_eq(b), Rc::ptr_eq(model_a, model_b));
+                        }
+                    }
+                }
+
+                fn step(&mut self, rng: &mut Rng) {
+                    let value = rng.next() as u32;
+                    if self.handles.is_empty() {
+                        self.new_cell(value);
+                        return;
+                    }
+                    let i = rng.below(self.handles.len());
+
+                    match rng.below(10) {
+                        0 => self.new_cell(value),
+                        1 => {
+                            let (cell, model) = &self.handles[i];
+                            let handle = (cell.clone(), model.clone());
+                            self.handles.push(handle);
+                        }
+                        2 => drop(self.handles.swap_remove(i)),
+                        3 => {
+                            let (cell, model) = self.handles.swap_remove(i);
+                            match cell.try_borrow_owned() {
+                                Ok(guard) => {
+                                    assert!(!model.borrowed.replace(true));
+                                    self.guards.push((guard, model));
+                                }
+                                Err(cell) => {
+                                    assert!(model.borrowed.get());
+                                    self.handles.push((cell, model));
+                                }
+                            }
+                        }
+                        4 if !self.guards.is_empty() => {
+                            let j = rng.below(self.guards.len());
+                            let (guard, model) = &mut self.guards[j];
+                            **guard = value;
+                            *model.value.borrow_mut() = value;
+                        }
+                        5 if !self.guards.is_empty() => {
+                            let j = rng.below(self.guards.len());
+                            let (guard, model) = self.guards.swap_remove(j);
+                            self.handles
+                                .push((OwnedRef::cell(&guard).clone(), model.clone()));
+                            drop(guard);
+                            model.borrowed.set(false);
+                        }
+                        6 => {
+                            let (cell, model) = &self.handles[i];
+                            match cell.try_borrow() {
+                                Some(mut borrowed) => {
+                                    assert!(!model.borrowed.get());
+                                    *borrowed = value;
+                                    *model.value.borrow_mut() = value;
+                                }
+                                None => assert!(model.borrowed.get()),
+                            }
+                        }
+                        7 => {
+                            let (cell, model) = self.handles.swap_remove(i);
+                            let ptr = cell.leak();
+                            // SAFETY: `ptr` was just leaked from a `ThinCell<u32>`
+                            let cell = unsafe { ThinCell::<u32>::from_raw(ptr) };
+                            self.handles.push((cell, model));
+                        }
+                        8 => {
+                            let (cell, model) = self.handles.swap_remove(i);
+                            let unique = Rc::strong_count(&model) == 1 && !model.borrowed.get();
+                            match cell.try_unwrap() {
+                                Ok(inner) => {
+                                    assert!(unique);
+                                    assert_eq!(inner, *model.value.borrow());
+                                }
+                                Err(cell) => {
+                                    assert!(!unique);
+                                    self.handles.push((cell, model));
+                                }
+                            }
+                        }
+                        // Unsizing a shared or borrowed cell panics
+                        9 if Rc::strong_count(&self.handles[i].1) == 1
+                            && !self.handles[i].1.borrowed.get() =>
+                        {
+                            let (cell, model) = self.handles.swap_remove(i);
+                            // SAFETY: `Inner<u32>` coerces to `Inner<dyn Any>`
+                            let erased = unsafe { cell.unsize::<dyn Any>(|p| p as _) };
+                            let cell = erased.downcast::<u32>().ok().unwrap();
+                            self.handles.push((cell, model));
+                        }
+                        _ => {}
+                    }
+                }
+
+                fn new_cell(&mut self, value: u32) {
+                    let model = Rc::new(Model {
+                        value: RefCell::new(value),
+                        borrowed: Cell::new(false),
+                    });
+                    self.handles.push((ThinCell::new(value), model));
+                }
+            }
+
+            #[test]
+            fn test_model() {
+                for seed in 0..SEEDS {
+                    let mut rng = Rng::new(seed);
+                    let mut harness = Harness {
+                        handles: Vec::new(),
+                        guards: Vec::new(),
+                    };
+                    for step in 0..STEPS {
+                        harness.step(&mut rng);
+                        harness.check(seed, step);
+                    }
+                }
+            }
+        }
+    };
+}
+
+model_test!(unsync);
+model_test!(sync);
@@ -0,0 +1,46 @@
+#![cfg(feature = "profile")]
+
+use std::sync::Mutex;
+
+use thin_cell::{
+    profile::{self, Allocation, Profiler},
+    unsync::ThinCell,
+};
+
+struct Recorder {
+    live: Mutex<Vec<Allocation>>,
+}
+
+impl Profiler for Recorder {
+    fn allocated(&self, allocation: &Allocation) {
+        self.live.lock().unwrap().push(*allocation);
+    }
+
+    fn freed(&self, addr: usize, size: usize) {
+        let mut live = self.live.lock().unwrap();
+        let index = live.iter().position(|a| a.addr == addr).unwrap();
+        assert_eq!(live.remove(index).size, size);
+    }
+}
+
+// The profiler is global, so everything lives in a single test
+#[test]
+fn test_profile() {
+    static RECORDER: Recorder = Recorder {
+        live: Mutex::new(Vec::new()),
+    };
+    assert!(profile::set_profiler(&RECORDER).is_ok());
+    assert!(profile::set_profiler(&RECORDER).is_err());
+
+    let line = line!() + 1;
+    let cell = ThinCell::new([0u32; 4]).unsize_slice();
+    let live = RECORDER.live.lock().unwrap().clone();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].addr, cell.addr());
+    assert_eq!(live[0].size, cell.allocated_size());
+    assert_eq!(live[0].type_name, "[u32; 4]");
+    assert_eq!(live[0].location.line(), line);
+
+    drop(cell);
+    assert!(RECORDER.live.lock().unwrap().is_empty());
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "erased-serde")]
+
+use std::any::Any;
+
+use thin_cell::{sync, unsync};
+
+#[test]
+fn test_serde_registry_round_trip() {
+    let mut registry = unsync::SerdeRegistry::default();
+    registry
+        .register::<u32>("count")
+        .register::<Vec<String>>("names");
+    assert!(registry.contains::<u32>());
+    assert!(!registry.contains::<i32>());
+
+    let cells = [
+        unsafe { unsync::ThinCell::<dyn Any>::new_unsize(7u32, |p| p as _) },
+        unsafe { unsync::ThinCell::<dyn Any>::new_unsize(vec![String::from("a")], |p| p as _) },
+    ];
+    let tagged = cells
+        .iter()
+        .map(|cell| registry.tagged(cell))
+        .collect::<Vec<_>>();
+    let json = serde_json::to_string(&tagged).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"type":"count","value":7},{"type":"names","value":["a"]}]"#
+    );
+
+    let values = serde_json::from_str::<Vec<serde_json::Value>>(&json).unwrap();
+    let names = registry.deserialize(&values[1]).unwrap();
+    let names = names.downcast::<Vec<String>>().ok().unwrap();
+    assert_eq!(*names.borrow(), ["a"]);
+}
+
+#[test]
+fn test_serde_registry_errors() {
+    let mut registry = sync::SerdeRegistry::new();
+    registry.register::<u32>("count");
+
+    // Unregistered or borrowed values can't be serialized
+    let cell = unsafe { sync::ThinCell::<dyn Any + Send + Sync>::new_unsize(1i8, |p| p as _) };
+    assert!(serde_json::to_string(&registry.tagged(&cell)).is_err());
+    let cell = unsafe { sync::ThinCell::<dyn Any + Send + Sync>::new_unsize(1u32, |p| p as _) };
+    let guard = cell.borrow();
+    assert!(serde_json::to_string(&registry.tagged(&cell)).is_err());
+    drop(guard);
+
+    let mut de = serde_json::Deserializer::from_str(r#"{"type":"other","value":1}"#);
+    assert!(registry.deserialize(&mut de).is_err());
+    let mut de = serde_json::Deserializer::from_str(r#"{"value":1,"type":"count"}"#);
+    assert!(registry.deserialize(&mut de).is_err());
+
+    // Renaming a type frees its old name
+    registry.register::<u32>("counter");
+    registry.register::<i8>("count");
+    assert_eq!(
+        format!("{registry:?}").len(),
+        r#"{"counter", "count"}"#.len()
+    );
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn test_serde_registry_duplicate_name() {
+    let mut registry = unsync::SerdeRegistry::new();
+    registry.register::<u32>("count").register::<u64>("count");
+}
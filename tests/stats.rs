@@ -0,0 +1,39 @@
+#![cfg(feature = "stats")]
+
+use thin_cell::{Stats, stats, sync, unsync};
+
+// Counters are global, so everything lives in a single test
+#[test]
+fn test_stats() {
+    assert_eq!(stats(), Stats::default());
+
+    let cell = unsync::ThinCell::new(0u64);
+    let clone = cell.clone();
+    let size = cell.allocated_size();
+    assert_eq!(
+        stats(),
+        Stats {
+            live: 1,
+            bytes: size
+        }
+    );
+
+    let slice = sync::ThinCell::new([0u8; 100]).unsize_slice();
+    let many = unsync::ThinCell::new_many([1u64, 2, 3]);
+    let mut slab = sync::ThinSlab::new();
+    let key = slab.insert(0u64);
+    assert_eq!(
+        stats(),
+        Stats {
+            live: 6,
+            bytes: 5 * size + slice.allocated_size(),
+        }
+    );
+
+    drop((cell, clone));
+    assert_eq!(stats().live, 5);
+
+    assert_eq!(slab.remove(key).unwrap().try_unwrap().unwrap(), 0);
+    drop((slice, many));
+    assert_eq!(stats(), Stats::default());
+}
@@ -19,6 +19,10 @@ fn test_send_sync_bounds() {
     assert_mt::<ThinCell<String>>();
     assert_mt::<ThinCell<[u8]>>();
     assert_mt::<ThinCell<dyn std::fmt::Debug + Send + Sync>>();
+    assert_mt::<OnceThinCell<String>>();
+    assert_mt::<LazyThinCell<String>>();
+    assert_mt::<Interner>();
+    assert_mt::<Ref<'static, String>>();
 }
 
 #[test]
@@ -2006,3 +2010,134 @@ fn test_unsize_operations_concurrent() {
     assert_eq!(handle1.join().unwrap(), 1);
     assert_eq!(handle2.join().unwrap(), 5);
 }
+
+#[test]
+fn test_new_many_drop_across_threads() {
+    let cells = ThinCell::new_many((0..64).map(|i| vec![i; 8]));
+    let barrier = Arc::new(Barrier::new(cells.len()));
+
+    let handles = cells
+        .into_iter()
+        .map(|cell| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let other = cell.clone();
+                barrier.wait();
+                other.borrow().push(0);
+                assert_eq!(cell.borrow().len(), 9);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn test_slab_release_across_threads() {
+    let mut slab = ThinSlab::new();
+    let keys = (0..64).map(|i| slab.insert(i)).collect::<Vec<_>>();
+
+    let handles = keys
+        .iter()
+        .map(|key| {
+            let cell = slab.remove(*key).unwrap();
+            thread::spawn(move || {
+                *cell.borrow() += 1;
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // All slots have been released by other threads and can be reused
+    assert!(slab.is_empty());
+    let key = slab.insert(0);
+    assert!(keys.iter().all(|old| *old != key));
+}
+
+#[test]
+fn test_thin_project() {
+    struct Pair {
+        left: Vec<u32>,
+        right: (u32, String),
+    }
+
+    thin_cell::thin_project! {
+        trait PairFields for sync::ThinCell<Pair> {
+            fn left -> Vec<u32> = left;
+            fn label -> String = right.1;
+        }
+    }
+
+    let cell = ThinCell::new(Pair {
+        left: vec![],
+        right: (0, String::new()),
+    });
+    let other = cell.clone();
+    thread::spawn(move || {
+        other.left().push(1);
+        other.label().push('x');
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(*cell.left(), [1]);
+    assert_eq!(*cell.label(), "x");
+    assert_eq!(cell.borrow().right.0, 0);
+}
+
+#[test]
+fn test_borrow_two_no_deadlock() {
+    let a = ThinCell::new(0);
+    let b = ThinCell::new(0);
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let (mut x, mut y) = if i % 2 == 0 {
+                        a.borrow_two(&b)
+                    } else {
+                        let (y, x) = b.borrow_two(&a);
+                        (x, y)
+                    };
+                    *x += 1;
+                    *y += 1;
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+
+    assert_eq!((*a.borrow(), *b.borrow()), (4000, 4000));
+}
+
+#[test]
+fn test_once_thin_cell_init_race() {
+    let cell = OnceThinCell::new();
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let barrier = Arc::new(Barrier::new(8));
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let (cell, calls, barrier) = (cell.clone(), calls.clone(), barrier.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                *cell.get_or_init(|| {
+                    calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    i
+                })
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+    assert!(results.iter().all(|&r| r == results[0]));
+    assert_eq!(cell.get(), Some(&results[0]));
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use thin_cell::unsync::ThinCell;
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+
+/// An event recorded by [`Recorder`], as its message and fields.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Recorded {
+    message: String,
+    addr: Option<u64>,
+    count: Option<u64>,
+}
+
+impl Visit for Recorded {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "addr" => self.addr = Some(value),
+            "count" => self.count = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<Recorded> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+fn event(message: &str, addr: usize, count: Option<u64>) -> Recorded {
+    Recorded {
+        message: message.into(),
+        addr: Some(addr as u64),
+        count,
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("thin_cell")
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut recorded = Recorded::default();
+        event.record(&mut recorded);
+        self.events.lock().unwrap().push(recorded);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn test_tracing_events() {
+    let recorder = Recorder::default();
+    let events = recorder.clone();
+
+    tracing::subscriber::with_default(recorder, || {
+        let cell = ThinCell::new(1);
+        let addr = cell.addr();
+        assert_eq!(events.take(), [event("new", addr, None)]);
+
+        let other = cell.clone();
+        assert_eq!(events.take(), [event("clone", addr, Some(2))]);
+
+        let guard = cell.borrow();
+        assert!(other.try_borrow().is_none());
+        drop(guard);
+        assert_eq!(
+            events.take(),
+            [
+                event("borrow", addr, None),
+                event("borrow conflict", addr, None)
+            ]
+        );
+
+        drop(other);
+        drop(cell);
+        assert_eq!(
+            events.take(),
+            [
+                event("drop", addr, Some(1)),
+                event("drop", addr, Some(0)),
+                event("free", addr, None)
+            ]
+        );
+    });
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "track-borrow")]
+
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use thin_cell::unsync::ThinCell;
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+    payload.downcast::<String>().map(|s| *s).unwrap()
+}
+
+#[test]
+fn test_conflict_reports_outstanding_borrow() {
+    let cell = ThinCell::new(0);
+
+    let line = line!() + 1;
+    let _guard = cell.borrow();
+
+    let message = panic_message(|| drop(cell.borrow()));
+    assert!(message.starts_with("Already borrowed"));
+    assert!(
+        message.contains(&format!("{}:{line}:", file!())),
+        "{message}"
+    );
+}
+
+#[test]
+fn test_conflict_reports_latest_borrow() {
+    let cell = ThinCell::new(0);
+    drop(cell.borrow());
+
+    let line = line!() + 1;
+    let _guard = cell.try_borrow().unwrap();
+
+    let message = panic_message(|| drop(cell.borrow()));
+    assert!(
+        message.contains(&format!("{}:{line}:", file!())),
+        "{message}"
+    );
+
+    // Released borrows are forgotten
+    drop(_guard);
+    drop(cell.borrow());
+}
+
+#[test]
+#[cfg(feature = "borrow-history")]
+fn test_borrow_history() {
+    use thin_cell::unsync::BorrowEventKind::*;
+
+    let cell = ThinCell::new(0);
+    assert!(cell.borrow_history().is_empty());
+
+    for _ in 0..10 {
+        *cell.borrow() += 1;
+    }
+    let line = line!() + 1;
+    let guard = cell.borrow();
+
+    let history = cell.borrow_history();
+    assert_eq!(history.len(), 8);
+    assert_eq!(history.last().unwrap().kind, Borrow);
+    assert_eq!(history.last().unwrap().location.line(), line);
+    assert_eq!(history[history.len() - 2].kind, Release);
+    assert!(history.windows(2).all(|w| w[0].at <= w[1].at));
+
+    let message = panic_message(|| drop(cell.borrow()));
+    assert_eq!(message.matches("\n  borrow of").count(), 4);
+    assert_eq!(message.matches("\n  release of").count(), 4);
+
+    drop(guard);
+    assert_eq!(cell.borrow_history().last().unwrap().kind, Release);
+}
+
+#[test]
+#[cfg(feature = "borrow-history")]
+fn test_borrow_history_forgotten_on_drop() {
+    let cell = ThinCell::new(0);
+    drop(cell.borrow());
+    assert_eq!(cell.borrow_history().len(), 2);
+
+    let state = cell.as_ptr();
+    drop(cell);
+
+    // A new cell at the same address must not inherit the history
+    let cell = ThinCell::new(0);
+    if cell.as_ptr() == state {
+        assert!(cell.borrow_history().is_empty());
+    }
+}
+
+#[test]
+#[cfg(feature = "borrow-history")]
+fn test_borrow_history_forgotten_on_into_shared() {
+    let cell = ThinCell::new(0);
+    drop(cell.borrow());
+    assert_eq!(cell.borrow_history().len(), 2);
+
+    // Back at the same address, the history of the first cell is gone
+    let cell = cell.into_shared().unwrap().into_local().unwrap();
+    assert!(cell.borrow_history().is_empty());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "triomphe")]
+
+use thin_cell::{sync, unsync};
+use triomphe::{Arc, ThinArc};
+
+#[test]
+fn test_arc_conversions() {
+    let cell = sync::ThinCell::from(Arc::new(String::from("a")));
+    assert_eq!(*cell.borrow(), "a");
+
+    // Shared `Arc`s are cloned
+    let arc = Arc::new(vec![1]);
+    let cell = unsync::ThinCell::from(arc.clone());
+    cell.borrow().push(2);
+    assert_eq!(*arc, [1]);
+
+    let other = cell.clone();
+    let cell = Arc::<Vec<i32>>::try_from(cell).err().unwrap();
+    drop(other);
+    assert_eq!(*Arc::<Vec<i32>>::try_from(cell).unwrap(), [1, 2]);
+}
+
+#[test]
+fn test_thin_arc_conversions() {
+    let arc = ThinArc::from_header_and_iter((), [1u8, 2, 3].into_iter());
+    let cell = sync::ThinCell::<[u8]>::from(&arc);
+    assert_eq!(*cell.borrow(), [1, 2, 3]);
+
+    let other = cell.clone();
+    let cell = ThinArc::<(), u8>::try_from(cell).err().unwrap();
+    drop(other);
+
+    let arc = ThinArc::<(), u8>::try_from(cell).unwrap();
+    assert_eq!(arc.slice, [1, 2, 3]);
+}
@@ -1,7 +1,237 @@
 mod common;
-use std::cell::Cell;
+use std::{cell::Cell, rc::Rc};
 
 use common::*;
 use thin_cell::unsync::*;
 
 testcases!();
+
+#[test]
+fn test_thin_slice_and_str() {
+    use thin_cell::{thin_slice, thin_str};
+
+    let cell: ThinCell<[u8]> = thin_slice![];
+    assert!(cell.borrow().is_empty());
+
+    let cell: ThinCell<[&str]> = thin_slice![unsync: "a", "b",];
+    assert_eq!(*cell.borrow(), ["a", "b"]);
+
+    let flag = Rc::new(Cell::new(0));
+    let cell: ThinCell<[Rc<Cell<i32>>]> = thin_slice![flag.clone(); 3];
+    assert_eq!(Rc::strong_count(&flag), 4);
+    drop(cell);
+    assert_eq!(Rc::strong_count(&flag), 1);
+
+    let cell: ThinCell<str> = thin_str!("");
+    assert_eq!(&*cell.borrow(), "");
+
+    let cell: ThinCell<str> = thin_str!("多字节");
+    assert_eq!(cell.borrow().chars().count(), 3);
+    assert_eq!(cell.allocated_size(), size_of::<Inner<[u8; 9]>>());
+}
+
+#[test]
+fn test_borrow_conflict_hook() {
+    thread_local! {
+        static CONFLICTS: Cell<Vec<(usize, u32)>> = const { Cell::new(Vec::new()) };
+    }
+
+    set_borrow_conflict_hook(|conflict| {
+        CONFLICTS.with(|c| {
+            let mut v = c.take();
+            v.push((conflict.addr, conflict.location.line()));
+            c.set(v);
+        })
+    });
+
+    let cell = ThinCell::new(1);
+    let guard = cell.borrow();
+    let line = line!() + 1;
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.borrow()));
+    assert!(res.is_err());
+    drop(guard);
+
+    assert!(take_borrow_conflict_hook().is_some());
+    assert!(take_borrow_conflict_hook().is_none());
+    assert_eq!(CONFLICTS.with(Cell::take), [(cell.addr(), line)]);
+}
+
+#[test]
+fn test_thread_local_cell() {
+    thin_cell::thread_local_cell! {
+        static VALUE: Vec<u32> = vec![1];
+        static EMPTY: String = String::new()
+    }
+
+    let handle = VALUE.with(|cell| cell.clone());
+    handle.borrow().push(2);
+    VALUE.with(|cell| {
+        assert!(cell.ptr_eq(&handle));
+        assert_eq!(*cell.borrow(), [1, 2]);
+    });
+    EMPTY.with(|cell| assert!(cell.borrow().is_empty()));
+
+    std::thread::spawn(|| VALUE.with(|cell| assert_eq!(*cell.borrow(), [1])))
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_into_shared() {
+    let cell = ThinCell::new(String::from("a"));
+    let other = cell.clone();
+    let cell = cell.into_shared().unwrap_err();
+    drop(other);
+
+    let guard = cell.borrow();
+    assert!(cell.clone().into_shared().is_err());
+    drop(guard);
+
+    let shared = cell.into_shared().unwrap();
+    let shared = std::thread::spawn(move || {
+        shared.borrow().push('b');
+        shared
+    })
+    .join()
+    .unwrap();
+
+    let other = shared.clone();
+    let shared = shared.into_local().unwrap_err();
+    drop(other);
+    let cell = shared.into_local().unwrap();
+    assert_eq!(cell.try_unwrap().unwrap(), "ab");
+
+    // Released through the non-atomic state of the block
+    let (a, b) = ThinCell::new_pair(1, 2);
+    let a = a.into_shared().unwrap_err();
+    drop(b);
+    assert!(a.into_shared().is_err());
+
+    let (a, b) = thin_cell::sync::ThinCell::new_pair(1, 2);
+    let a = a.into_local().unwrap_err();
+    drop(b);
+    assert!(a.into_local().is_err());
+}
+
+#[test]
+fn test_into_send() {
+    let cell = ThinCell::new(String::from("a"));
+    let other = cell.clone();
+    let cell = cell.into_send().unwrap_err();
+    drop(other);
+
+    let guard = cell.borrow();
+    assert!(cell.clone().into_send().is_err());
+    drop(guard);
+
+    let token = cell.into_send().unwrap();
+    let token = std::thread::spawn(move || {
+        let cell = ThinCell::from(token);
+        cell.borrow().push('b');
+        cell.into_send().unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(token.into_inner().try_unwrap().unwrap(), "ab");
+
+    let (a, b) = ThinCell::new_pair(1, 2);
+    let a = a.into_send().unwrap_err();
+    drop(b);
+    assert!(a.into_send().is_err());
+}
+
+#[test]
+fn test_oneshot() {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use thin_cell::unsync::oneshot::{self, RecvError};
+
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let wakes = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let (tx, rx) = oneshot::channel();
+    let mut rx = pin!(rx);
+    assert_eq!(rx.try_recv(), Ok(None));
+    assert!(rx.as_mut().poll(&mut cx).is_pending());
+    tx.send(String::from("done")).unwrap();
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert_eq!(rx.poll(&mut cx), Poll::Ready(Ok(String::from("done"))));
+
+    let (tx, rx) = oneshot::channel::<u32>();
+    let mut rx = pin!(rx);
+    assert!(rx.as_mut().poll(&mut cx).is_pending());
+    drop(tx);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 2);
+    assert!(matches!(
+        rx.poll(&mut cx),
+        Poll::Ready(Err(RecvError { .. }))
+    ));
+
+    let (tx, rx) = oneshot::channel();
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(1), Err(1));
+}
+
+#[test]
+fn test_drop_deferred() {
+    use thin_cell::{deferred_len, drain_deferred};
+
+    struct Node(Rc<Cell<usize>>, Option<ThinCell<Node>>);
+
+    impl Drop for Node {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+            // Defer the rest of the chain instead of recursing
+            if let Some(next) = self.1.take() {
+                next.drop_deferred();
+            }
+        }
+    }
+
+    let dropped = Rc::new(Cell::new(0));
+    let mut head = None;
+    for _ in 0..3 {
+        head = Some(ThinCell::new(Node(dropped.clone(), head)));
+    }
+    let head = head.unwrap();
+    let other = head.clone();
+
+    head.drop_deferred();
+    assert_eq!(deferred_len(), 0);
+    other.drop_deferred();
+    assert_eq!(deferred_len(), 1);
+    assert_eq!(dropped.get(), 0);
+
+    assert_eq!(drain_deferred(2), 2);
+    assert_eq!(dropped.get(), 2);
+    assert_eq!(deferred_len(), 1);
+    assert_eq!(drain_deferred(usize::MAX), 1);
+    assert_eq!(dropped.get(), 3);
+    assert_eq!(drain_deferred(usize::MAX), 0);
+
+    // Queued values are dropped when the thread exits
+    let value = std::sync::Arc::new(());
+    let captured = value.clone();
+    std::thread::spawn(move || ThinCell::new(captured).drop_deferred())
+        .join()
+        .unwrap();
+    assert_eq!(std::sync::Arc::strong_count(&value), 1);
+}
@@ -0,0 +1,16 @@
+#![cfg(all(
+    feature = "wasm-send",
+    target_arch = "wasm32",
+    target_os = "unknown",
+    not(target_feature = "atomics")
+))]
+
+use thin_cell::unsync::ThinCell;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_unsync_cell_is_send() {
+    assert_send_sync::<ThinCell<u32>>();
+    assert_send_sync::<ThinCell<dyn std::any::Any + Send>>();
+}